@@ -1,4 +1,11 @@
 use clap::{Parser, Subcommand};
+use container_compose::container::{
+    DEFAULT_CONTAINER_BINARY, DEFAULT_START_TIMEOUT, DEFAULT_STOP_TIMEOUT,
+};
+use container_compose::ui::{AnsiMode, OutputFormat};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 #[derive(Parser)]
 #[command(name = "container-compose")]
 #[command(about = "A Docker Compose-like tool for Apple's container framework")]
@@ -8,9 +15,49 @@ pub struct Cli {
     #[arg(short, long, default_value = "container-compose.yml")]
     pub file: String,
 
-    /// Enable verbose logging
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// Enable verbose logging (-v echoes every command run, -vv also its output)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Base directory for relative paths (defaults to the compose file's directory)
+    #[arg(long, global = true)]
+    pub project_directory: Option<PathBuf>,
+
+    /// Directory holding named volume data (defaults to $CONTAINER_COMPOSE_HOME/volumes or ~/.container-compose/volumes)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub volumes_dir: Option<PathBuf>,
+
+    /// Control colored output and spinners
+    #[arg(long, value_enum, global = true, default_value = "auto")]
+    pub ansi: AnsiMode,
+
+    /// Path to the `container` executable to run (defaults to `container` in PATH)
+    #[arg(
+        long,
+        global = true,
+        env = "CONTAINER_COMPOSE_CONTAINER_BINARY",
+        value_name = "PATH",
+        default_value = DEFAULT_CONTAINER_BINARY
+    )]
+    pub container_binary: PathBuf,
+
+    /// Print output as a stream of JSON objects, one per line, instead of text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Enable services in this profile (repeatable, or comma-separated in COMPOSE_PROFILES)
+    #[arg(
+        long = "profile",
+        global = true,
+        env = "COMPOSE_PROFILES",
+        value_delimiter = ',',
+        value_name = "NAME"
+    )]
+    pub profiles: Vec<String>,
+
+    /// Project name (defaults to the top-level `name:` or the project directory name)
+    #[arg(short, long, global = true, env = "COMPOSE_PROJECT_NAME")]
+    pub project_name: Option<String>,
 
     #[command(subcommand)]
     pub command: Commands,
@@ -24,9 +71,51 @@ pub enum Commands {
         #[arg(short, long)]
         detach: bool,
 
-        /// Recreate containers
+        /// Recreate containers even if their definition is unchanged
         #[arg(long)]
         force_recreate: bool,
+
+        /// Never recreate existing containers, even if their definition changed
+        #[arg(long, conflicts_with = "force_recreate")]
+        no_recreate: bool,
+
+        /// Stop all containers if any container exits (attached mode only)
+        #[arg(long, conflicts_with = "detach")]
+        abort_on_container_exit: bool,
+
+        /// Return this service's exit code (implies --abort-on-container-exit)
+        #[arg(long, value_name = "SERVICE", conflicts_with = "detach")]
+        exit_code_from: Option<String>,
+
+        /// Seconds to wait for each container to start before failing
+        #[arg(long, default_value_t = DEFAULT_START_TIMEOUT)]
+        start_timeout: u64,
+
+        /// Wait for every service to be healthy (or running, without a healthcheck)
+        #[arg(long)]
+        wait: bool,
+
+        /// Seconds to wait for every service to become healthy (implies --wait);
+        /// failing shows a health table and the last healthcheck output
+        #[arg(long, value_name = "SECONDS")]
+        wait_timeout: Option<u64>,
+
+        /// Seconds each container must stay running after start before the next
+        /// one starts; one that exits fails `up` with its last log lines
+        #[arg(long, value_name = "SECONDS")]
+        confirm_running: Option<u64>,
+
+        /// Remove containers for services that are no longer in the config
+        #[arg(long)]
+        remove_orphans: bool,
+
+        /// Run N containers for a service, named <project>-<service>-1..N (repeatable)
+        #[arg(long, value_name = "SERVICE=N", value_parser = parse_scale)]
+        scale: Vec<(String, usize)>,
+
+        /// Keep going when a service fails, and report every failure at the end
+        #[arg(long)]
+        keep_going: bool,
     },
 
     /// Stop and remove containers (like docker-compose down)
@@ -34,6 +123,26 @@ pub enum Commands {
         /// Remove volumes as well
         #[arg(short, long)]
         volumes: bool,
+
+        /// Seconds to wait for a graceful stop before killing (0 kills immediately)
+        #[arg(short, long, default_value_t = DEFAULT_STOP_TIMEOUT)]
+        timeout: u64,
+
+        /// Delete volume data without asking for confirmation
+        #[arg(short = 'y', long, visible_alias = "force")]
+        yes: bool,
+
+        /// Also stop and remove containers of services no longer in the config
+        #[arg(long, visible_alias = "include-orphans")]
+        remove_orphans: bool,
+
+        /// Keep going when a service fails, and report every failure at the end
+        #[arg(long)]
+        keep_going: bool,
+
+        /// Maximum number of services to stop at once (unlimited by default)
+        #[arg(long, env = "COMPOSE_PARALLEL_LIMIT", value_name = "N")]
+        parallel: Option<NonZeroUsize>,
     },
 
     /// Show container logs
@@ -45,13 +154,49 @@ pub enum Commands {
         #[arg(short, long)]
         follow: bool,
 
-        /// Number of lines to show from the end
+        /// Number of lines to show from the end of each container's log, or 'all' [default: 100 unless following]
+        #[arg(long)]
+        tail: Option<LogTail>,
+
+        /// Only print lines containing this text (case-sensitive, not matching the service prefix)
+        #[arg(long, value_name = "PATTERN")]
+        grep: Option<String>,
+
+        /// Don't colorize the service name prefix
+        #[arg(long)]
+        no_color: bool,
+
+        /// Don't print the service name prefix
         #[arg(long)]
-        tail: Option<usize>,
+        no_log_prefix: bool,
     },
 
     /// List containers
-    Ps,
+    Ps {
+        /// Format each row with a template, e.g. '{{.Service}} {{.Status}}'
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Only print service names, one per line
+        #[arg(long, conflicts_with_all = ["format", "quiet"])]
+        services: bool,
+
+        /// Only print container IDs
+        #[arg(short, long, conflicts_with = "format")]
+        quiet: bool,
+
+        /// Only show services matching a filter, e.g. status=running (repeatable)
+        #[arg(long, value_name = "KEY=VALUE")]
+        filter: Vec<String>,
+
+        /// Add a RESTARTS column, flagging containers restarted more than N times [default: 3]
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "3")]
+        restarts: Option<u32>,
+
+        /// List containers of services no longer in the config, as `down --remove-orphans` would remove
+        #[arg(long)]
+        orphans: bool,
+    },
 
     /// Build or rebuild services
     Build {
@@ -61,6 +206,10 @@ pub enum Commands {
         /// Don't use cache when building
         #[arg(long)]
         no_cache: bool,
+
+        /// Always attempt to pull a newer version of the base image
+        #[arg(long)]
+        pull: bool,
     },
 
     /// Execute a command in a running container
@@ -78,35 +227,281 @@ pub enum Commands {
         /// Allocate a pseudo-TTY
         #[arg(short, long)]
         tty: bool,
+
+        /// Working directory inside the container (defaults to the service's working_dir)
+        #[arg(short, long)]
+        workdir: Option<String>,
+    },
+
+    /// Run a one-off command in a new container for a service
+    Run {
+        /// Service name
+        service: String,
+
+        /// Command to run instead of the service's command
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+
+        /// Working directory inside the container
+        #[arg(short, long)]
+        workdir: Option<String>,
+
+        /// Run as this user (name or uid[:gid])
+        #[arg(short, long)]
+        user: Option<String>,
+
+        /// Set an environment variable (KEY=VALUE), overriding the service's value
+        #[arg(short, long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+
+        /// Don't allocate a pseudo-TTY
+        #[arg(short = 'T', long = "no-TTY")]
+        no_tty: bool,
+
+        /// Publish the service's ports (not done by default to avoid colliding with running instances)
+        #[arg(long)]
+        service_ports: bool,
+
+        /// Remove the container when the command exits
+        #[arg(long)]
+        rm: bool,
     },
 
     /// Pull images for services
     Pull {
         /// Service name to pull (optional)
         service: Option<String>,
+
+        /// Also pull the images of the service's dependencies, transitively
+        #[arg(long, requires = "service")]
+        with_deps: bool,
+
+        /// Keep going when a service fails, and report every failure at the end
+        #[arg(long)]
+        keep_going: bool,
     },
 
     /// Restart services
     Restart {
         /// Service name to restart (optional)
         service: Option<String>,
+
+        /// Also restart services that depend on the named service
+        #[arg(long, alias = "deps")]
+        cascade: bool,
+    },
+
+    /// Pull newer images and recreate the services whose image changed
+    Update {
+        /// Service name to update (optional)
+        service: Option<String>,
+
+        /// Also restart services that depend on a recreated service
+        #[arg(long)]
+        cascade: bool,
     },
 
-    /// Stop services
+    /// Stop services without removing their containers
     Stop {
         /// Service name to stop (optional)
         service: Option<String>,
+
+        /// Seconds to wait for a graceful stop before killing (0 kills immediately)
+        #[arg(short, long, default_value_t = DEFAULT_STOP_TIMEOUT)]
+        timeout: u64,
+
+        /// Also stop and remove containers of services no longer in the config
+        #[arg(long, visible_alias = "include-orphans", conflicts_with = "service")]
+        remove_orphans: bool,
+
+        /// Keep going when a service fails, and report every failure at the end
+        #[arg(long)]
+        keep_going: bool,
+    },
+
+    /// Kill services' containers right away, without a graceful stop
+    Kill {
+        /// Service name to kill (optional)
+        service: Option<String>,
+
+        /// Also kill and remove containers of services no longer in the config
+        #[arg(long, visible_alias = "include-orphans", conflicts_with = "service")]
+        remove_orphans: bool,
+
+        /// Keep going when a service fails, and report every failure at the end
+        #[arg(long)]
+        keep_going: bool,
     },
 
-    /// Start services
+    /// Start the existing, stopped containers of services
     Start {
         /// Service name to start (optional)
         service: Option<String>,
     },
+
+    /// Validate the configuration and print the resolved file
+    Config {
+        /// Output format for the resolved config or validation errors
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
+        /// Only print the effective project name
+        #[arg(long)]
+        project_name: bool,
+
+        /// Pin each image to the digest of the locally available image
+        #[arg(long)]
+        resolve_image_digests: bool,
+    },
+
+    /// Print the configuration as a standard docker-compose file
+    #[command(alias = "translate")]
+    Convert,
+
+    /// Manage named volumes
+    Volume {
+        #[command(subcommand)]
+        command: VolumeCommands,
+    },
+
+    /// Inspect project networks
+    Network {
+        #[command(subcommand)]
+        command: NetworkCommands,
+    },
+
+    /// Stream service start/stop/die events until Ctrl-C
+    Events {
+        /// Output format for each event (json prints one object per line)
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
+        /// Only show events matching a filter, e.g. service=web (repeatable)
+        #[arg(long, value_name = "KEY=VALUE")]
+        filter: Vec<String>,
+    },
+
+    /// Report environment details for bug reports
+    #[command(alias = "version")]
+    Doctor,
+}
+
+#[derive(Subcommand)]
+pub enum VolumeCommands {
+    /// List named volumes with their location and size
+    Ls,
+
+    /// Show a volume's path and the services that mount it
+    Inspect {
+        /// Volume name
+        name: String,
+    },
+
+    /// Archive a volume's data into a .tar.gz file
+    Backup {
+        /// Volume name
+        name: String,
+
+        /// Archive to write
+        archive: PathBuf,
+    },
+
+    /// Restore a volume's data from a .tar.gz file
+    Restore {
+        /// Volume name
+        name: String,
+
+        /// Archive to read
+        archive: PathBuf,
+
+        /// Overwrite a volume that already has data without asking
+        #[arg(short = 'y', long, visible_alias = "yes")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NetworkCommands {
+    /// List declared networks and whether they exist
+    Ls,
+
+    /// Show a network's details and the services attached to it
+    Inspect {
+        /// Network name
+        name: String,
+    },
+}
+
+// Parse a `--scale SERVICE=N` value
+fn parse_scale(s: &str) -> Result<(String, usize), String> {
+    let (service, replicas) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected SERVICE=N, got '{}'", s))?;
+    let replicas = replicas
+        .parse()
+        .map_err(|_| format!("expected a number of containers, got '{}'", replicas))?;
+    Ok((service.to_string(), replicas))
+}
+
+// How much history `logs --tail` asks for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogTail {
+    All,
+    Lines(usize),
+}
+
+impl FromStr for LogTail {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(LogTail::All);
+        }
+        s.parse()
+            .map(LogTail::Lines)
+            .map_err(|_| format!("expected a number of lines or 'all', got '{}'", s))
+    }
+}
+
+impl Commands {
+    // Whether the command's output is meant to be consumed by scripts
+    pub fn is_scripted(&self) -> bool {
+        matches!(
+            self,
+            Commands::Ps {
+                format: Some(_),
+                ..
+            } | Commands::Ps { services: true, .. }
+                | Commands::Ps { quiet: true, .. }
+                | Commands::Events {
+                    format: OutputFormat::Json,
+                    ..
+                }
+        )
+    }
 }
 
 impl Cli {
     pub fn parse_args() -> Self {
         Self::parse()
     }
+
+    // Resolve the directory relative paths in the config are based on
+    pub fn project_directory(&self) -> PathBuf {
+        let dir = match &self.project_directory {
+            Some(dir) => dir.clone(),
+            None => Path::new(&self.file)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+
+        if dir.is_absolute() {
+            dir
+        } else {
+            std::env::current_dir()
+                .map(|cwd| cwd.join(&dir))
+                .unwrap_or(dir)
+        }
+    }
 }
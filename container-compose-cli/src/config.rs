@@ -1,23 +1,188 @@
+use crate::error::ComposeError;
+use indexmap::IndexMap;
 use serde::{Deserialize, Deserializer, Serialize};
-use serde_yaml::Value;
-use std::collections::HashMap;
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ContainerComposeConfig {
-    #[serde(default = "default_version")]
+    #[serde(default = "default_version", deserialize_with = "deserialize_version")]
     pub version: String,
-    pub services: HashMap<String, Service>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // Kept in declaration order so listings and operations are the same on every run
+    pub services: IndexMap<String, Service>,
     #[serde(default)]
-    pub volumes: HashMap<String, Volume>,
+    pub volumes: IndexMap<String, Volume>,
     #[serde(default)]
-    pub networks: HashMap<String, Network>,
+    pub networks: IndexMap<String, Network>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub configs: IndexMap<String, ConfigFile>,
+    // Files merged in by `from_file`; not written back out, as their content already is
+    #[serde(default, skip_serializing)]
+    pub include: Vec<String>,
+    #[serde(flatten, deserialize_with = "deserialize_extensions")]
+    pub extensions: HashMap<String, Value>,
+    // Docker service fields found in the file that container-compose ignores, as (service, field)
+    #[serde(skip)]
+    pub ignored_fields: Vec<(String, String)>,
+    // Services with `command: []` in the raw file; it is read as unset, so the
+    // image's default command still runs
+    #[serde(skip)]
+    pub empty_commands: Vec<String>,
+    // Services left out by `apply_profiles`; their containers aren't orphans
+    #[serde(skip)]
+    pub disabled_services: Vec<String>,
+}
+
+// Docker Compose service fields that are recognized but have no equivalent here, so
+// they are dropped with a warning rather than silently
+const UNSUPPORTED_SERVICE_FIELDS: &[&str] = &[
+    "cap_add",
+    "cap_drop",
+    "cpu_count",
+    "cpu_percent",
+    "cpu_period",
+    "cpu_quota",
+    "cpu_shares",
+    "cpus",
+    "cpuset",
+    "deploy",
+    "devices",
+    "dns",
+    "dns_search",
+    "domainname",
+    "env_file",
+    "expose",
+    "external_links",
+    "extra_hosts",
+    "group_add",
+    "hostname",
+    "ipc",
+    "labels",
+    "links",
+    "logging",
+    "mac_address",
+    "mem_limit",
+    "mem_reservation",
+    "mem_swappiness",
+    "memswap_limit",
+    "network_mode",
+    "oom_kill_disable",
+    "oom_score_adj",
+    "pid",
+    "platform",
+    "privileged",
+    "pull_policy",
+    "read_only",
+    "restart",
+    "scale",
+    "secrets",
+    "security_opt",
+    "shm_size",
+    "stop_grace_period",
+    "stop_signal",
+    "sysctls",
+    "tmpfs",
+    "ulimits",
+    "user",
+    "userns_mode",
+];
+
+// Known-but-unsupported fields each service sets in the raw file, sorted by service
+fn find_ignored_fields(root: &Value) -> Vec<(String, String)> {
+    let Some(Value::Mapping(services)) = root.get("services") else {
+        return Vec::new();
+    };
+
+    let mut ignored: Vec<(String, String)> = services
+        .iter()
+        .filter_map(|(name, service)| Some((name.as_str()?, service.as_mapping()?)))
+        .flat_map(|(name, service)| {
+            service
+                .keys()
+                .filter_map(Value::as_str)
+                .filter(|key| UNSUPPORTED_SERVICE_FIELDS.contains(key))
+                .map(move |key| (name.to_string(), key.to_string()))
+        })
+        .collect();
+    ignored.sort();
+    ignored
+}
+
+// The services that set `command` to an empty list in the raw file, sorted
+fn find_empty_commands(root: &Value) -> Vec<String> {
+    let Some(Value::Mapping(services)) = root.get("services") else {
+        return Vec::new();
+    };
+
+    let mut empty: Vec<String> = services
+        .iter()
+        .filter(|(_, service)| {
+            service
+                .get("command")
+                .and_then(Value::as_sequence)
+                .is_some_and(|list| list.is_empty())
+        })
+        .filter_map(|(name, _)| Some(name.as_str()?.to_string()))
+        .collect();
+    empty.sort();
+    empty
 }
 
 fn default_version() -> String {
     "1.0".to_string()
 }
 
+// File format versions whose semantics container-compose implements
+const SUPPORTED_VERSIONS: &[&str] = &["1.0"];
+
+// Custom deserializer for the version that accepts `version: 3.8` as well as
+// `version: "3.8"`, normalized to `major.minor` with both parts as integers
+fn deserialize_version<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let version = match Value::deserialize(deserializer)? {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => {
+            return Err(serde::de::Error::custom(
+                "version must be a string or number",
+            ));
+        }
+    };
+
+    let mut parts = version.split('.');
+    let major = parts.next().and_then(|major| major.parse::<u32>().ok());
+    let minor = parts
+        .next()
+        .map_or(Some(0), |minor| minor.parse::<u32>().ok());
+    match (major, minor) {
+        (Some(major), Some(minor)) => Ok(format!("{}.{}", major, minor)),
+        _ => Err(serde::de::Error::custom(format!(
+            "version must be `major.minor`, got '{}'",
+            version
+        ))),
+    }
+}
+
+// The `version` exactly as written, for an unquoted one that YAML read as a
+// float and so lost its trailing zeros (`3.10` would otherwise be 3.1)
+fn raw_numeric_version(contents: &str, root: &Value) -> Option<Value> {
+    root.get("version")?.as_f64()?;
+    let line = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("version:"))?;
+    let raw = line.split('#').next()?.trim();
+    Some(Value::String(raw.to_string()))
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Service {
+    #[serde(default)]
     pub image: String,
     #[serde(default)]
     pub ports: Vec<String>,
@@ -27,8 +192,581 @@ pub struct Service {
     pub environment: Vec<String>,
     #[serde(default)]
     pub depends_on: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_command",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub command: Option<Vec<String>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_entrypoint",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub working_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_init",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub init: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_tty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tty: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_stdin_open",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stdin_open: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_timeout: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_service_networks")]
+    pub networks: HashMap<String, ServiceNetwork>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_service_configs",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub configs: Vec<ServiceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<HealthCheck>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_build",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub build: Option<Build>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<Extends>,
+    // Only started when one of these profiles is active; always started when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+    #[serde(flatten, deserialize_with = "deserialize_extensions")]
+    pub extensions: HashMap<String, Value>,
+}
+
+// How to build a service's image; the result is tagged with the service's `image`,
+// or `<project>-<service>` when it has none
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Build {
+    #[serde(default = "default_build_context")]
+    pub context: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_environment",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub args: Vec<String>,
+}
+
+fn default_build_context() -> String {
+    ".".to_string()
+}
+
+// Custom deserializer for `build`: a plain string is the context directory
+fn deserialize_build<'de, D>(deserializer: D) -> Result<Option<Build>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        Value::String(context) => Ok(Some(Build {
+            context,
+            dockerfile: None,
+            args: Vec::new(),
+        })),
+        value @ Value::Mapping(_) => serde_yaml::from_value(value)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        _ => Err(serde::de::Error::custom(
+            "build must be a context path or a mapping",
+        )),
+    }
+}
+
+// How to tell whether a service is healthy, run inside its container
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HealthCheck {
+    #[serde(deserialize_with = "deserialize_health_test")]
+    pub test: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_period: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disable: bool,
+}
+
+// Custom deserializer for healthcheck tests: a plain string runs through a shell
+// (`CMD-SHELL`), a list is kept as-is (`["CMD", ...]`, `["CMD-SHELL", ...]` or `["NONE"]`)
+fn deserialize_health_test<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => Ok(vec!["CMD-SHELL".to_string(), s]),
+        Value::Sequence(seq) => seq
+            .into_iter()
+            .map(|item| {
+                item.as_str().map(str::to_string).ok_or_else(|| {
+                    serde::de::Error::custom("healthcheck test items must be strings")
+                })
+            })
+            .collect(),
+        _ => Err(serde::de::Error::custom(
+            "healthcheck test must be a string or a list of strings",
+        )),
+    }
+}
+
+impl HealthCheck {
+    // The command to run in the container, or None when the check is disabled
+    pub fn command(&self) -> Option<Vec<String>> {
+        if self.disable {
+            return None;
+        }
+        match self.test.split_first()? {
+            (kind, args) if kind == "CMD" && !args.is_empty() => Some(args.to_vec()),
+            (kind, args) if kind == "CMD-SHELL" && !args.is_empty() => {
+                Some(vec!["sh".to_string(), "-c".to_string(), args.join(" ")])
+            }
+            _ => None,
+        }
+    }
+
+    // Time between checks (defaults to 5s so `up --wait` stays responsive)
+    pub fn interval(&self) -> Duration {
+        parse_duration_or(self.interval.as_deref(), Duration::from_secs(5))
+    }
+
+    // How long a single check may run before it counts as failed
+    pub fn timeout(&self) -> Duration {
+        parse_duration_or(self.timeout.as_deref(), Duration::from_secs(30))
+    }
+
+    // Consecutive failures before the service is considered unhealthy
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(3)
+    }
+
+    // Grace period after start during which failures don't count
+    pub fn start_period(&self) -> Duration {
+        parse_duration_or(self.start_period.as_deref(), Duration::ZERO)
+    }
+}
+
+// Parse an optional duration, falling back to a default (validation rejects bad values)
+fn parse_duration_or(value: Option<&str>, default: Duration) -> Duration {
+    value
+        .and_then(|v| parse_duration(v).ok())
+        .unwrap_or(default)
+}
+
+// Parse a compose duration such as `30s`, `1m30s`, `500ms` or `1h`
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let mut total = Duration::ZERO;
+    let mut rest = value.trim();
+
+    if rest.is_empty() {
+        return Err("empty duration".to_string());
+    }
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid duration '{}'", value))?;
+        rest = &rest[digits..];
+
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit] {
+            "h" => number * 3600.0,
+            "m" => number * 60.0,
+            "s" => number,
+            "ms" => number / 1000.0,
+            "us" => number / 1_000_000.0,
+            _ => return Err(format!("invalid duration '{}'", value)),
+        };
+        rest = &rest[unit..];
+        total += Duration::from_secs_f64(seconds);
+    }
+
+    Ok(total)
+}
+
+// Per-network options for a service (the long `networks` mapping form)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ServiceNetwork {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv4_address: Option<String>,
+}
+
+// Custom deserializer for service networks that handles both list and mapping formats
+fn deserialize_service_networks<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, ServiceNetwork>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+
+    match value {
+        // List format: ["frontend", "backend"]
+        Value::Sequence(seq) => Ok(seq
+            .into_iter()
+            .filter_map(|item| {
+                item.as_str()
+                    .map(|name| (name.to_string(), Default::default()))
+            })
+            .collect()),
+        // Mapping format: {backend: {aliases: [db], ipv4_address: 10.0.0.5}, frontend: }
+        Value::Mapping(map) => {
+            let mut networks = HashMap::new();
+            for (key, options) in map {
+                let Some(name) = key.as_str() else {
+                    continue;
+                };
+                let options = if options.is_null() {
+                    ServiceNetwork::default()
+                } else {
+                    serde_yaml::from_value(options).map_err(serde::de::Error::custom)?
+                };
+                networks.insert(name.to_string(), options);
+            }
+            Ok(networks)
+        }
+        _ => Ok(HashMap::new()),
+    }
+}
+
+// A non-sensitive file that services can have mounted, declared under top-level `configs`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ConfigFile {
+    pub file: String,
+}
+
+// A service's use of a declared config: where it lands in the container
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ServiceConfig {
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    // Reserved for future chmod handling; the file is mounted read-only as-is
+    #[serde(
+        default,
+        deserialize_with = "deserialize_mode",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mode: Option<u32>,
+}
+
+// Custom deserializer for file modes, always read as octal like chmod: serde_yaml
+// hands `0440` over as a string, while a bare `440` arrives as the number 440,
+// so its digits are read again as octal. A `0o440` literal arrives already
+// converted, so it has to be quoted to be told apart.
+fn deserialize_mode<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => n
+            .as_u64()
+            .and_then(|n| u32::from_str_radix(&n.to_string(), 8).ok())
+            .map(Some)
+            .ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "invalid mode {}: modes are octal, quote it like '0440'",
+                    n
+                ))
+            }),
+        Value::String(s) => {
+            let digits = s.strip_prefix("0o").unwrap_or(&s);
+            u32::from_str_radix(digits, 8)
+                .map(Some)
+                .map_err(|_| serde::de::Error::custom(format!("invalid octal mode '{}'", s)))
+        }
+        Value::Null => Ok(None),
+        _ => Err(serde::de::Error::custom(
+            "mode must be a number or octal string",
+        )),
+    }
+}
+
+impl Service {
+    // Whether the service runs with the given profiles active
+    pub fn is_enabled(&self, active_profiles: &[String]) -> bool {
+        self.profiles.is_empty()
+            || self
+                .profiles
+                .iter()
+                .any(|profile| active_profiles.contains(profile))
+    }
+
+    // Stable fingerprint of the fields that require a new container when they change
+    pub fn config_hash(&self) -> String {
+        // Sorted, with each network's aliases and address, so the hash is stable
+        let networks: BTreeMap<&String, &ServiceNetwork> = self.networks.iter().collect();
+
+        let definition = serde_json::json!({
+            "image": self.image,
+            "environment": self.environment,
+            "volumes": self.volumes,
+            "command": self.command,
+            "entrypoint": self.entrypoint,
+            "init": self.init,
+            "tty": self.tty,
+            "stdin_open": self.stdin_open,
+            "ports": self.ports,
+            "working_dir": self.working_dir,
+            "networks": networks,
+            "container_name": self.container_name,
+            "configs": self.configs,
+            "healthcheck": self.healthcheck,
+        });
+
+        // FNV-1a, so the hash never changes between runs or builds
+        let hash = definition
+            .to_string()
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        format!("{:016x}", hash)
+    }
+
+    // Copy of the service with `${VAR}` references in environment, build args,
+    // entrypoint and command resolved; environment values and build args see the
+    // host environment, and entrypoint and command additionally see the service's
+    // own environment. Bare `KEY` environment entries take the host's value.
+    pub fn interpolated(
+        &self,
+        name: &str,
+        host_env: &HashMap<String, String>,
+    ) -> Result<Service, ComposeError> {
+        let resolve = |field: String, value: &str, env: &HashMap<String, String>| {
+            interpolate(value, env).map_err(|reason| ComposeError::Interpolation {
+                service: name.to_string(),
+                field,
+                reason,
+            })
+        };
+
+        let mut service = self.clone();
+        let mut env = host_env.clone();
+
+        let mut environment = Vec::new();
+        for item in &service.environment {
+            // A bare `KEY` passes the host's value through, and is left out when unset
+            let (key, value) = match item.split_once('=') {
+                Some((key, value)) => (
+                    key,
+                    resolve(format!("environment.{}", key), value, host_env)?,
+                ),
+                None => match host_env.get(item) {
+                    Some(value) => (item.as_str(), value.clone()),
+                    None => continue,
+                },
+            };
+            env.insert(key.to_string(), value.clone());
+            environment.push(format!("{}={}", key, value));
+        }
+        service.environment = environment;
+
+        for item in service
+            .build
+            .iter_mut()
+            .flat_map(|build| build.args.iter_mut())
+        {
+            let Some((key, value)) = item.split_once('=') else {
+                continue;
+            };
+            let value = resolve(format!("build.args.{}", key), value, host_env)?;
+            *item = format!("{}={}", key, value);
+        }
+
+        for (field, args) in [
+            ("entrypoint", &mut service.entrypoint),
+            ("command", &mut service.command),
+        ] {
+            for (index, arg) in args.iter_mut().flatten().enumerate() {
+                *arg = resolve(format!("{}[{}]", field, index), arg, &env)?;
+            }
+        }
+
+        Ok(service)
+    }
+}
+
+// Expand `$VAR`, `${VAR}`, `${VAR:-default}`, `${VAR-default}`, `${VAR:?message}` and
+// `${VAR?message}` like docker-compose; unset variables without a default expand to
+// an empty string and `$$` is a literal `$`
+pub fn interpolate(input: &str, env: &HashMap<String, String>) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        if c != '$' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some((_, '$')) => {
+                chars.next();
+                output.push('$');
+            }
+            Some((open, '{')) => {
+                chars.next();
+                // Find the matching brace, allowing `${...}` inside a default
+                let mut depth = 1;
+                let mut close = None;
+                for (i, c) in chars.by_ref() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                close = Some(i);
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                let close = close.ok_or_else(|| format!("unterminated '${{' in '{}'", input))?;
+                output.push_str(&expand(&input[open + 1..close], env)?);
+            }
+            Some((start, c)) if c == '_' || c.is_ascii_alphabetic() => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c != '_' && !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                if let Some(value) = env.get(&input[start..end]) {
+                    output.push_str(value);
+                }
+            }
+            _ => output.push('$'),
+        }
+    }
+
+    Ok(output)
+}
+
+// Expand the inside of a `${...}` expression
+fn expand(expression: &str, env: &HashMap<String, String>) -> Result<String, String> {
+    let name_len = expression
+        .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+        .unwrap_or(expression.len());
+    let (name, modifier) = expression.split_at(name_len);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!("invalid variable reference '${{{}}}'", expression));
+    }
+
+    let value = env.get(name);
+    // The `:` forms treat an empty value like an unset one
+    let (unset, operator) = match modifier.strip_prefix(':') {
+        Some(rest) => (value.is_none_or(|v| v.is_empty()), rest),
+        None => (value.is_none(), modifier),
+    };
+
+    match operator.chars().next() {
+        None if modifier.is_empty() => Ok(value.cloned().unwrap_or_default()),
+        Some('-') if unset => interpolate(&operator[1..], env),
+        Some('?') if unset => {
+            let message = interpolate(&operator[1..], env)?;
+            if message.is_empty() {
+                Err(format!("required variable '{}' is not set", name))
+            } else {
+                Err(format!(
+                    "required variable '{}' is not set: {}",
+                    name, message
+                ))
+            }
+        }
+        Some('-' | '?') => Ok(value.cloned().unwrap_or_default()),
+        _ => Err(format!("invalid variable reference '${{{}}}'", expression)),
+    }
+}
+
+impl ServiceConfig {
+    // Path inside the container, defaulting to `/<source>` like docker-compose
+    pub fn target_path(&self) -> String {
+        self.target
+            .clone()
+            .unwrap_or_else(|| format!("/{}", self.source))
+    }
+}
+
+// Custom deserializer for service configs that handles both short (name) and long (mapping) forms
+fn deserialize_service_configs<'de, D>(deserializer: D) -> Result<Vec<ServiceConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+
+    let Value::Sequence(seq) = value else {
+        return Ok(Vec::new());
+    };
+
+    let mut configs = Vec::new();
+    for item in seq {
+        match item {
+            Value::String(source) => configs.push(ServiceConfig {
+                source,
+                target: None,
+                mode: None,
+            }),
+            Value::Mapping(_) => {
+                configs.push(serde_yaml::from_value(item).map_err(serde::de::Error::custom)?)
+            }
+            _ => {}
+        }
+    }
+    Ok(configs)
+}
+
+// Reference to a base service, optionally defined in another compose file
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Extends {
+    pub service: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+// Keep `x-` extension fields (preserved but otherwise ignored) and drop any other unknown keys
+fn deserialize_extensions<'de, D>(deserializer: D) -> Result<HashMap<String, Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let fields = HashMap::<String, Value>::deserialize(deserializer)?;
+    Ok(fields
+        .into_iter()
+        .filter(|(key, _)| key.starts_with("x-"))
+        .collect())
 }
 
 // Custom deserializer for environment that handles both array and object formats
@@ -49,11 +787,16 @@ where
             }
             Ok(env_vars)
         }
-        // Object format: {KEY: value, KEY2: value2}
+        // Object format: {KEY: value, KEY2: value2}; `KEY:` with no value is a bare `KEY`
         Value::Mapping(map) => {
             let mut env_vars = Vec::new();
             for (key, value) in map {
-                if let (Some(key_str), Some(value_str)) = (key.as_str(), value.as_str()) {
+                let Some(key_str) = key.as_str() else {
+                    continue;
+                };
+                if value.is_null() {
+                    env_vars.push(key_str.to_string());
+                } else if let Some(value_str) = value.as_str() {
                     env_vars.push(format!("{key_str}={value_str}"));
                 }
             }
@@ -66,6 +809,83 @@ where
     }
 }
 
+// Custom deserializers for boolean service flags that reject anything else with a clear message
+fn deserialize_init<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_flag(deserializer, "init")
+}
+
+fn deserialize_tty<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_flag(deserializer, "tty")
+}
+
+fn deserialize_stdin_open<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_flag(deserializer, "stdin_open")
+}
+
+fn deserialize_flag<'de, D>(deserializer: D, field: &str) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Bool(b) => Ok(Some(b)),
+        Value::Null => Ok(None),
+        other => Err(serde::de::Error::custom(format!(
+            "{} must be true or false, got {}",
+            field,
+            serde_yaml::to_string(&other).unwrap_or_default().trim()
+        ))),
+    }
+}
+
+// Custom deserializer for entrypoint: the command forms, except that an empty
+// list is kept, as it clears the image's entrypoint
+fn deserialize_entrypoint<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Sequence(seq) if seq.is_empty() => Ok(Some(Vec::new())),
+        value => deserialize_command(value).map_err(serde::de::Error::custom),
+    }
+}
+
+// Custom deserializer for command and entrypoint that handles both list and string formats
+fn deserialize_command<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        // An empty list can't clear the image's default, so it counts as unset
+        Value::Sequence(seq) if seq.is_empty() => Ok(None),
+        // List format: ["npm", "start"], passed to the container as-is
+        Value::Sequence(seq) => seq
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s),
+                Value::Number(n) => Ok(n.to_string()),
+                Value::Bool(b) => Ok(b.to_string()),
+                _ => Err(serde::de::Error::custom("list items must be strings")),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        // String format: "npm start", run through a shell like Docker does
+        Value::String(s) => Ok(Some(vec!["sh".to_string(), "-c".to_string(), s])),
+        Value::Null => Ok(None),
+        _ => Err(serde::de::Error::custom(
+            "expected a string or a list of strings",
+        )),
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Volume {
     #[serde(default)]
@@ -76,39 +896,957 @@ pub struct Volume {
 pub struct Network {
     #[serde(default = "default_driver")]
     pub driver: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipam: Option<Ipam>,
+}
+
+// IP address management settings for a network
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Ipam {
+    #[serde(default)]
+    pub config: Vec<IpamConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct IpamConfig {
+    pub subnet: Option<String>,
+}
+
+impl Network {
+    // The first subnet declared for this network, if any
+    pub fn subnet(&self) -> Option<&str> {
+        self.ipam
+            .as_ref()?
+            .config
+            .iter()
+            .find_map(|c| c.subnet.as_deref())
+    }
 }
 
 fn default_driver() -> String {
     "bridge".to_string()
 }
 
+// Service fields whose lists are concatenated (rather than replaced) when extending
+const MERGED_LIST_FIELDS: &[&str] = &["ports", "volumes", "environment", "depends_on", "networks"];
+
+// Read a YAML file into a raw value with merge keys applied
+fn load_yaml(path: &Path) -> Result<Value, ComposeError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| ComposeError::ConfigRead {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut value: Value = serde_yaml::from_str(&contents)?;
+    if let Some(version) = raw_numeric_version(&contents, &value)
+        && let Some(root) = value.as_mapping_mut()
+    {
+        root.insert(Value::from("version"), version);
+    }
+    // Resolve `<<: *anchor` merge keys so shared blocks can be reused across services
+    value.apply_merge()?;
+    Ok(value)
+}
+
+// Get the `services` mapping of a raw compose document
+fn services_mapping(root: &Value) -> Mapping {
+    root.get("services")
+        .and_then(Value::as_mapping)
+        .cloned()
+        .unwrap_or_default()
+}
+
+// Top-level sections whose entries an including file overrides one by one
+const MERGED_SECTIONS: &[&str] = &["services", "volumes", "networks", "configs"];
+
+// Merge the files a top-level `include` lists (relative to `file`) underneath
+// the document, in order, so the document's own definitions win over theirs.
+// `chain` holds the files being loaded, to report include cycles.
+fn resolve_includes(
+    root: &mut Value,
+    file: &Path,
+    chain: &mut Vec<PathBuf>,
+) -> Result<(), ComposeError> {
+    let Some(includes) = root.get("include") else {
+        return Ok(());
+    };
+    let includes: Vec<String> = serde_yaml::from_value(includes.clone()).map_err(|e| {
+        ComposeError::InvalidInclude(format!("Invalid include in '{}': {}", file.display(), e))
+    })?;
+
+    let dir = file.parent().unwrap_or_else(|| Path::new("."));
+    let mut merged = Mapping::new();
+    for include in &includes {
+        let path = dir.join(include);
+        let canonical = path
+            .canonicalize()
+            .map_err(|source| ComposeError::ConfigRead {
+                path: path.clone(),
+                source,
+            })?;
+        if chain.contains(&canonical) {
+            let mut cycle: Vec<String> = chain.iter().map(|p| p.display().to_string()).collect();
+            cycle.push(canonical.display().to_string());
+            return Err(ComposeError::InvalidInclude(format!(
+                "Circular include detected: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        chain.push(canonical);
+        let mut included = load_yaml(&path)?;
+        resolve_includes(&mut included, &path, chain)?;
+        resolve_extends(&mut included, &path)?;
+        chain.pop();
+        if let Some(base) = Path::new(include).parent() {
+            rebase_paths(&mut included, base);
+        }
+        merge_document(&mut merged, &included);
+    }
+
+    merge_document(&mut merged, root);
+    *root = Value::Mapping(merged);
+    Ok(())
+}
+
+// Make the relative paths of an included document (bind mount sources, build
+// contexts and config files) relative to the including file instead, by
+// prefixing them with `base`, the included file's directory as listed
+fn rebase_paths(root: &mut Value, base: &Path) {
+    if base.as_os_str().is_empty() {
+        return;
+    }
+    let rebase = |path: &str| -> String {
+        if Path::new(path).is_absolute() || path.starts_with('~') {
+            return path.to_string();
+        }
+        let path = match path.strip_prefix("./").unwrap_or(path) {
+            "" | "." => base.to_path_buf(),
+            path => base.join(path),
+        };
+        let path = path.to_string_lossy();
+        if path.starts_with('/') || path.starts_with('.') {
+            path.to_string()
+        } else {
+            format!("./{}", path)
+        }
+    };
+
+    if let Some(services) = root.get_mut("services").and_then(Value::as_mapping_mut) {
+        for service in services.values_mut().filter_map(Value::as_mapping_mut) {
+            if let Some(volumes) = service.get_mut("volumes").and_then(Value::as_sequence_mut) {
+                for volume in volumes {
+                    let Some((source, rest)) = volume.as_str().and_then(|v| v.split_once(':'))
+                    else {
+                        continue;
+                    };
+                    // Bare names are named volumes, not paths
+                    if source.starts_with('.') || source.contains('/') {
+                        *volume = Value::String(format!("{}:{}", rebase(source), rest));
+                    }
+                }
+            }
+            match service.get_mut("build") {
+                Some(Value::String(context)) => *context = rebase(context),
+                Some(Value::Mapping(build)) => {
+                    let context = build.get("context").and_then(Value::as_str).unwrap_or(".");
+                    let context = rebase(context);
+                    build.insert(Value::from("context"), Value::String(context));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(configs) = root.get_mut("configs").and_then(Value::as_mapping_mut) {
+        for config in configs.values_mut().filter_map(Value::as_mapping_mut) {
+            if let Some(Value::String(file)) = config.get_mut("file") {
+                *file = rebase(file);
+            }
+        }
+    }
+}
+
+// Lay one compose document over another: entries of the merged sections are
+// replaced one by one, anything else as a whole
+fn merge_document(base: &mut Mapping, overlay: &Value) {
+    let Some(overlay) = overlay.as_mapping() else {
+        return;
+    };
+
+    for (key, value) in overlay {
+        let section = key.as_str().is_some_and(|k| MERGED_SECTIONS.contains(&k));
+        match (base.get_mut(key), value) {
+            (Some(Value::Mapping(entries)), Value::Mapping(overrides)) if section => {
+                for (name, definition) in overrides {
+                    entries.insert(name.clone(), definition.clone());
+                }
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+// Replace every service that uses `extends` with its fully merged definition
+fn resolve_extends(root: &mut Value, file: &Path) -> Result<(), ComposeError> {
+    let services = services_mapping(root);
+    let mut resolved = Mapping::new();
+
+    for (name, _) in &services {
+        let Some(name_str) = name.as_str() else {
+            continue;
+        };
+        let mut chain = Vec::new();
+        let service = resolve_service(file, &services, name_str, &mut chain)?;
+        resolved.insert(name.clone(), Value::Mapping(service));
+    }
+
+    if let Some(root) = root.as_mapping_mut() {
+        root.insert(Value::from("services"), Value::Mapping(resolved));
+    }
+    Ok(())
+}
+
+// Resolve a single service, following its `extends` chain
+fn resolve_service(
+    file: &Path,
+    services: &Mapping,
+    name: &str,
+    chain: &mut Vec<(PathBuf, String)>,
+) -> Result<Mapping, ComposeError> {
+    let key = (file.to_path_buf(), name.to_string());
+    if chain.contains(&key) {
+        let mut path: Vec<String> = chain.iter().map(|(_, service)| service.clone()).collect();
+        path.push(name.to_string());
+        return Err(ComposeError::InvalidExtends(format!(
+            "Circular extends detected: {}",
+            path.join(" -> ")
+        )));
+    }
+
+    let service = services
+        .get(name)
+        .and_then(Value::as_mapping)
+        .ok_or_else(|| {
+            ComposeError::InvalidExtends(format!(
+                "Cannot extend service '{}': not defined in '{}'",
+                name,
+                file.display()
+            ))
+        })?;
+
+    let Some(extends) = service.get("extends") else {
+        return Ok(service.clone());
+    };
+    let extends: Extends = serde_yaml::from_value(extends.clone()).map_err(|e| {
+        ComposeError::InvalidExtends(format!("Service '{}' has an invalid extends: {}", name, e))
+    })?;
+
+    chain.push(key);
+    let mut merged = match &extends.file {
+        Some(base_file) => {
+            let base_path = file
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(base_file);
+            let base_root = load_yaml(&base_path)?;
+            let base_services = services_mapping(&base_root);
+            resolve_service(&base_path, &base_services, &extends.service, chain)?
+        }
+        None => resolve_service(file, services, &extends.service, chain)?,
+    };
+    chain.pop();
+
+    merged.remove("extends");
+    for (field, value) in service {
+        let merged_list = field
+            .as_str()
+            .is_some_and(|f| MERGED_LIST_FIELDS.contains(&f));
+
+        match (merged.get_mut(field), value) {
+            (Some(Value::Sequence(base)), Value::Sequence(overrides)) if merged_list => {
+                for item in overrides {
+                    if !base.contains(item) {
+                        base.push(item.clone());
+                    }
+                }
+            }
+            (Some(Value::Mapping(base)), Value::Mapping(overrides)) if merged_list => {
+                for (k, v) in overrides {
+                    base.insert(k.clone(), v.clone());
+                }
+            }
+            _ => {
+                merged.insert(field.clone(), value.clone());
+            }
+        }
+    }
+
+    Ok(merged)
+}
+
 impl ContainerComposeConfig {
-    pub fn from_file(path: &str) -> anyhow::Result<Self> {
-        let contents = std::fs::read_to_string(path)?;
-        let config: ContainerComposeConfig = serde_yaml::from_str(&contents)?;
+    pub fn from_file(path: &str) -> Result<Self, ComposeError> {
+        let path = Path::new(path);
+        let mut value = load_yaml(path)?;
+        let mut chain: Vec<PathBuf> = path.canonicalize().into_iter().collect();
+        resolve_includes(&mut value, path, &mut chain)?;
+        resolve_extends(&mut value, path)?;
+        let ignored_fields = find_ignored_fields(&value);
+        let empty_commands = find_empty_commands(&value);
+        let mut config: ContainerComposeConfig = serde_yaml::from_value(value)?;
+        config.ignored_fields = ignored_fields;
+        config.empty_commands = empty_commands;
         Ok(config)
     }
-    pub fn validate(&self) -> anyhow::Result<()> {
-        // Check if all services have valid images
-        for (name, service) in &self.services {
-            if service.image.is_empty() {
-                return Err(anyhow::anyhow!("Service '{}' has no image specified", name));
+    // Resolve the effective project name: an explicit override (`-p` or
+    // COMPOSE_PROJECT_NAME), then the top-level `name:`, then the project directory
+    pub fn project_name(&self, override_name: Option<&str>, project_dir: &Path) -> String {
+        if let Some(name) = override_name.or(self.name.as_deref()) {
+            return name.to_string();
+        }
+
+        let dir_name = project_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        normalize_project_name(&dir_name)
+    }
+
+    // Declared named volumes mounted by at least one service, in declaration order
+    pub fn mounted_volumes(&self) -> Vec<&str> {
+        self.volumes
+            .keys()
+            .map(String::as_str)
+            .filter(|name| {
+                self.services
+                    .values()
+                    .flat_map(|service| &service.volumes)
+                    .any(|volume| volume.split(':').next() == Some(*name))
+            })
+            .collect()
+    }
+
+    // Problems worth pointing out that don't stop the file from being used
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if !SUPPORTED_VERSIONS.contains(&self.version.as_str()) {
+            warnings.push(format!(
+                "Unrecognized version '{}' (supported: {}); docker-compose semantics may differ",
+                self.version,
+                SUPPORTED_VERSIONS.join(", ")
+            ));
+        }
+
+        let mounted = self.mounted_volumes();
+        let unused: Vec<&str> = self
+            .volumes
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !mounted.contains(name))
+            .collect();
+        if !unused.is_empty() {
+            warnings.push(format!(
+                "Volume(s) declared but not mounted by any service: {}",
+                unused.join(", ")
+            ));
+        }
+
+        let mut services: Vec<&str> = self
+            .ignored_fields
+            .iter()
+            .map(|(s, _)| s.as_str())
+            .collect();
+        services.dedup();
+        for service in services {
+            let fields: Vec<&str> = self
+                .ignored_fields
+                .iter()
+                .filter(|(s, _)| s == service)
+                .map(|(_, field)| field.as_str())
+                .collect();
+            warnings.push(format!(
+                "Service '{}' sets Docker field(s) container-compose does not support, ignoring: {}",
+                service,
+                fields.join(", ")
+            ));
+        }
+
+        for service in &self.empty_commands {
+            warnings.push(format!(
+                "Service '{}' sets an empty command, which leaves the image's default command in place; to replace it, list the command to run instead",
+                service
+            ));
+        }
+
+        warnings
+    }
+
+    // Drop the services none of the active profiles enable, remembering their names
+    pub fn apply_profiles(&mut self, active_profiles: &[String]) {
+        let disabled = &mut self.disabled_services;
+        self.services.retain(|name, service| {
+            let enabled = service.is_enabled(active_profiles);
+            if !enabled {
+                disabled.push(name.clone());
             }
+            enabled
+        });
+    }
+
+    // Check the whole configuration, collecting every problem instead of stopping at the first
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if let Some(name) = &self.name
+            && !is_valid_project_name(name)
+        {
+            errors.push(ValidationError {
+                field: "name".to_string(),
+                service: None,
+                message: format!(
+                    "Invalid project name '{}': use lowercase letters, digits, '-' and '_', starting with a letter or digit",
+                    name
+                ),
+            });
         }
 
-        // Check dependencies exist
         for (name, service) in &self.services {
+            // Check the service has an image, or a build section to make one
+            if service.image.is_empty() && service.build.is_none() {
+                errors.push(ValidationError::service(
+                    name,
+                    "image",
+                    format!("Service '{}' has no image or build specified", name),
+                ));
+            }
+
+            // Check the image reference, including any pinned digest
+            if !service.image.is_empty()
+                && let Err(reason) = validate_image_reference(&service.image)
+            {
+                errors.push(ValidationError::service(
+                    name,
+                    "image",
+                    format!(
+                        "Service '{}' has invalid image '{}': {}",
+                        name, service.image, reason
+                    ),
+                ));
+            }
+
+            // Check dependencies exist
             for dep in &service.depends_on {
                 if !self.services.contains_key(dep) {
-                    return Err(anyhow::anyhow!(
-                        "Service '{}' depends on '{}' which doesn't exist",
+                    errors.push(ValidationError::service(
                         name,
-                        dep
+                        "depends_on",
+                        format!(
+                            "Service '{}' depends on '{}' which doesn't exist",
+                            name, dep
+                        ),
                     ));
                 }
             }
+
+            // Check port mappings are well formed
+            for port in &service.ports {
+                if let Err(reason) = validate_port(port) {
+                    errors.push(ValidationError::service(
+                        name,
+                        "ports",
+                        format!("Service '{}' has invalid port '{}': {}", name, port, reason),
+                    ));
+                }
+            }
+
+            // Check volume mounts reference declared named volumes
+            for volume in &service.volumes {
+                let Some((source, target)) = volume.split_once(':') else {
+                    errors.push(ValidationError::service(
+                        name,
+                        "volumes",
+                        format!(
+                            "Service '{}' uses anonymous volume '{}', which is not supported",
+                            name, volume
+                        ),
+                    ));
+                    continue;
+                };
+
+                // Check the optional mode segment
+                let mode = match target.split_once(':') {
+                    Some((_, mode)) if mode.contains(':') => {
+                        Err("too many ':' segments".to_string())
+                    }
+                    Some((_, mode)) => parse_volume_mode(mode).map(|_| ()),
+                    None => Ok(()),
+                };
+                if let Err(reason) = mode {
+                    errors.push(ValidationError::service(
+                        name,
+                        "volumes",
+                        format!(
+                            "Service '{}' has invalid volume '{}': {}",
+                            name, volume, reason
+                        ),
+                    ));
+                }
+
+                let is_path = source.starts_with('.')
+                    || source.starts_with('/')
+                    || source.starts_with('~')
+                    || source.contains('/');
+                if !is_path && !self.volumes.contains_key(source) {
+                    errors.push(ValidationError::service(
+                        name,
+                        "volumes",
+                        format!(
+                            "Service '{}' references undefined volume '{}'",
+                            name, source
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // Check explicit container names are unique across services
+        let mut container_names: HashMap<&str, &str> = HashMap::new();
+        for (name, service) in &self.services {
+            let Some(container_name) = service.container_name.as_deref() else {
+                continue;
+            };
+            if let Some(other) = container_names.insert(container_name, name) {
+                errors.push(ValidationError::service(
+                    name,
+                    "container_name",
+                    format!(
+                        "Service '{}' uses container_name '{}' already taken by service '{}'",
+                        name, container_name, other
+                    ),
+                ));
+            }
+        }
+
+        // Check service networks exist and static addresses fit their subnet
+        for (name, service) in &self.services {
+            for (network_name, options) in sorted(&service.networks) {
+                let network = self.networks.get(network_name);
+                if network.is_none() && network_name != "default" {
+                    errors.push(ValidationError::service(
+                        name,
+                        "networks",
+                        format!(
+                            "Service '{}' references undefined network '{}'",
+                            name, network_name
+                        ),
+                    ));
+                }
+
+                let Some(address) = &options.ipv4_address else {
+                    continue;
+                };
+                let Ok(ip) = address.parse::<Ipv4Addr>() else {
+                    errors.push(ValidationError::service(
+                        name,
+                        "networks",
+                        format!("Service '{}' has invalid ipv4_address '{}'", name, address),
+                    ));
+                    continue;
+                };
+
+                if let Some(subnet) = network.and_then(Network::subnet) {
+                    match subnet_contains(subnet, ip) {
+                        Some(true) => {}
+                        Some(false) => errors.push(ValidationError::service(
+                            name,
+                            "networks",
+                            format!(
+                                "Service '{}' ipv4_address {} is outside subnet {} of network '{}'",
+                                name, ip, subnet, network_name
+                            ),
+                        )),
+                        None => errors.push(ValidationError {
+                            field: "networks".to_string(),
+                            service: None,
+                            message: format!(
+                                "Network '{}' has invalid subnet '{}'",
+                                network_name, subnet
+                            ),
+                        }),
+                    }
+                }
+            }
         }
 
-        Ok(())
+        // Check healthcheck durations parse
+        for (name, service) in &self.services {
+            let Some(healthcheck) = &service.healthcheck else {
+                continue;
+            };
+            for (field, value) in [
+                ("interval", &healthcheck.interval),
+                ("timeout", &healthcheck.timeout),
+                ("start_period", &healthcheck.start_period),
+            ] {
+                if let Some(value) = value
+                    && let Err(reason) = parse_duration(value)
+                {
+                    errors.push(ValidationError::service(
+                        name,
+                        "healthcheck",
+                        format!(
+                            "Service '{}' has invalid healthcheck {}: {}",
+                            name, field, reason
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // Check configs used by services are declared
+        for (name, service) in &self.services {
+            for config in &service.configs {
+                if !self.configs.contains_key(&config.source) {
+                    errors.push(ValidationError::service(
+                        name,
+                        "configs",
+                        format!(
+                            "Service '{}' references undefined config '{}'",
+                            name, config.source
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // Check for dependency cycles so no command starts a stack that can't be ordered
+        for cycle in self.dependency_cycles() {
+            errors.push(ValidationError::service(
+                &cycle[0],
+                "depends_on",
+                format!("Circular dependency detected: {}", cycle.join(" -> ")),
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
+
+    // Check that no enabled service depends on one the active profiles leave out,
+    // which would otherwise only surface once `up` is underway
+    pub fn validate_profiles(
+        &self,
+        active_profiles: &[String],
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for (name, service) in &self.services {
+            if !service.is_enabled(active_profiles) {
+                continue;
+            }
+            for dep in &service.depends_on {
+                let Some(dependency) = self.services.get(dep) else {
+                    continue;
+                };
+                if !dependency.is_enabled(active_profiles) {
+                    errors.push(ValidationError::service(
+                        name,
+                        "depends_on",
+                        format!(
+                            "Service '{}' depends on '{}', which is only enabled by profile(s) {}; enable it with --profile {}",
+                            name,
+                            dep,
+                            dependency.profiles.join(", "),
+                            dependency.profiles[0]
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Check that files referenced by top-level `configs` exist under the project directory
+    pub fn validate_files(&self, project_dir: &Path) -> Result<(), Vec<ValidationError>> {
+        let mut errors: Vec<ValidationError> = self
+            .configs
+            .iter()
+            .filter(|(_, config)| !resolve_path(project_dir, &config.file).is_file())
+            .map(|(name, config)| ValidationError {
+                field: "configs".to_string(),
+                service: None,
+                message: format!(
+                    "Config '{}' file does not exist: {}",
+                    name,
+                    resolve_path(project_dir, &config.file).display()
+                ),
+            })
+            .collect();
+
+        for (name, service) in &self.services {
+            let Some(build) = &service.build else {
+                continue;
+            };
+            let context = resolve_path(project_dir, &build.context);
+            if !context.is_dir() {
+                errors.push(ValidationError::service(
+                    name,
+                    "build",
+                    format!(
+                        "Service '{}' build context does not exist: {}",
+                        name,
+                        context.display()
+                    ),
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Find every `depends_on` cycle, each reported as a path ending where it started
+    fn dependency_cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        let mut visited = HashSet::new();
+        let mut path = Vec::new();
+
+        for name in self.services.keys() {
+            self.find_cycles(name, &mut path, &mut visited, &mut cycles);
+        }
+
+        cycles
+    }
+
+    // Depth-first walk that records a cycle whenever it reaches a service already on the path
+    fn find_cycles<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        visited: &mut HashSet<&'a str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.to_string());
+            cycles.push(cycle);
+            return;
+        }
+
+        if !visited.insert(name) {
+            return;
+        }
+
+        if let Some(service) = self.services.get(name) {
+            path.push(name);
+            for dep in &service.depends_on {
+                self.find_cycles(dep, path, visited, cycles);
+            }
+            path.pop();
+        }
+    }
+}
+
+// A single problem found while validating the configuration
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationError {
+    pub field: String,
+    pub service: Option<String>,
+    pub message: String,
+}
+
+impl ValidationError {
+    // Create an error attached to a service field
+    fn service(service: &str, field: &str, message: String) -> Self {
+        Self {
+            field: field.to_string(),
+            service: Some(service.to_string()),
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// Resolve a path from the config against the project directory
+pub fn resolve_path(project_dir: &Path, path: &str) -> PathBuf {
+    if Path::new(path).is_absolute() {
+        PathBuf::from(path)
+    } else {
+        project_dir.join(path.strip_prefix("./").unwrap_or(path))
+    }
+}
+
+// Project names must be lowercase alphanumerics, '-' or '_', starting with a letter or digit
+pub fn is_valid_project_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+// Turn a directory name into a valid project name
+fn normalize_project_name(name: &str) -> String {
+    let normalized: String = name
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    let normalized = normalized.trim_start_matches(['-', '_']).to_string();
+
+    if normalized.is_empty() {
+        "default".to_string()
+    } else {
+        normalized
+    }
+}
+
+// Iterate a map in key order so reports are stable
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries: Vec<(&String, &V)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+// Whether an IPv4 address falls within a CIDR subnet (None if the subnet is malformed)
+fn subnet_contains(subnet: &str, ip: Ipv4Addr) -> Option<bool> {
+    let (base, prefix) = subnet.split_once('/')?;
+    let base: Ipv4Addr = base.parse().ok()?;
+    let prefix: u32 = prefix.parse().ok().filter(|p| *p <= 32)?;
+    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+    Some(u32::from(base) & mask == u32::from(ip) & mask)
+}
+
+// Check an image reference of the form `name[:tag][@sha256:<digest>]`
+fn validate_image_reference(image: &str) -> Result<(), String> {
+    if image.chars().any(char::is_whitespace) {
+        return Err("must not contain whitespace".to_string());
+    }
+
+    let (name, digest) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (image, None),
+    };
+
+    if name.is_empty() {
+        return Err("missing image name".to_string());
+    }
+
+    if let Some(digest) = digest {
+        let hex = digest
+            .strip_prefix("sha256:")
+            .ok_or_else(|| "digest must start with 'sha256:'".to_string())?;
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("sha256 digest must be 64 hexadecimal characters".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// Strip any `@digest` suffix from an image reference
+pub fn image_without_digest(image: &str) -> &str {
+    image.split_once('@').map_or(image, |(name, _)| name)
+}
+
+// Check a port mapping of the form `[ip:][host:]container[/protocol]`
+fn validate_port(spec: &str) -> Result<(), String> {
+    let (mapping, protocol) = match spec.split_once('/') {
+        Some((mapping, protocol)) => (mapping, Some(protocol)),
+        None => (spec, None),
+    };
+
+    if let Some(protocol) = protocol
+        && protocol != "tcp"
+        && protocol != "udp"
+    {
+        return Err(format!("unknown protocol '{}'", protocol));
+    }
+
+    let parts: Vec<&str> = mapping.split(':').collect();
+    let ports = match parts.len() {
+        1 | 2 => &parts[..],
+        3 => &parts[1..],
+        _ => return Err("expected [ip:][host:]container".to_string()),
+    };
+
+    for port in ports {
+        // Allow ranges like 8000-8010
+        for number in port.split('-') {
+            match number.parse::<u16>() {
+                Ok(n) if n > 0 => {}
+                _ => return Err(format!("'{}' is not a valid port number", port)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Volume mount options the container framework understands
+const SUPPORTED_VOLUME_MODES: &[&str] = &["ro", "rw"];
+
+// Docker mount options with no equivalent here, dropped with a warning
+const DOCKER_ONLY_VOLUME_MODES: &[&str] = &[
+    "z",
+    "Z",
+    "cached",
+    "delegated",
+    "consistent",
+    "nocopy",
+    "shared",
+    "rshared",
+    "slave",
+    "rslave",
+    "private",
+    "rprivate",
+];
+
+// Split a volume `:mode` segment into the options to keep and the
+// Docker-only options to drop, rejecting anything unknown
+pub fn parse_volume_mode(mode: &str) -> Result<(Vec<&str>, Vec<&str>), String> {
+    let mut kept = Vec::new();
+    let mut dropped = Vec::new();
+
+    for option in mode.split(',') {
+        if SUPPORTED_VOLUME_MODES.contains(&option) {
+            kept.push(option);
+        } else if DOCKER_ONLY_VOLUME_MODES.contains(&option) {
+            dropped.push(option);
+        } else {
+            return Err(format!(
+                "unsupported mode '{}' (expected {})",
+                option,
+                SUPPORTED_VOLUME_MODES.join(" or ")
+            ));
+        }
+    }
+
+    if kept.contains(&"ro") && kept.contains(&"rw") {
+        return Err("'ro' and 'rw' cannot be combined".to_string());
+    }
+
+    Ok((kept, dropped))
 }
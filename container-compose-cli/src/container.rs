@@ -1,560 +1,2735 @@
-use crate::config::{ContainerComposeConfig, Service};
-use crate::ui::UI;
-use anyhow::Result;
+use crate::config::{
+    Build, ContainerComposeConfig, Service, ServiceNetwork, parse_volume_mode, resolve_path,
+};
+use crate::error::{ComposeError, Result};
+use crate::ui::{OutputFormat, Reporter, ServiceAction};
+use futures_util::stream::{self, StreamExt};
+use indicatif::HumanBytes;
+use serde::Serialize;
 use std::collections::HashMap;
-use std::process::Stdio;
-use tokio::process::Command as AsyncCommand;
-use tokio::time::{Duration, timeout};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio::process::{Child, Command as AsyncCommand};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant, timeout};
 
 // Enum in Rust - like constants but more powerful
 #[derive(Debug, Clone, PartialEq)]
 pub enum ContainerStatus {
     Running,
+    Failed,
 }
 
-// Struct to represent a running container
+// Struct to represent a container started by this run, keyed by container name
 #[derive(Debug, Clone)]
 pub struct Container {
+    pub service: String,
     pub status: ContainerStatus,
-    pub container_id: Option<String>,
 }
 
-// Main container manager
-pub struct ContainerManager {
-    containers: HashMap<String, Container>,
-    config: ContainerComposeConfig,
-}
+// Seconds to wait for a graceful stop before killing a container
+pub const DEFAULT_STOP_TIMEOUT: u64 = 10;
 
-impl ContainerManager {
-    pub fn new(config: ContainerComposeConfig) -> Self {
-        Self {
-            containers: HashMap::new(),
-            config,
-        }
-    }
+// Seconds to wait for `container run` before giving up on a service
+pub const DEFAULT_START_TIMEOUT: u64 = 300;
 
-    // Start all services (like docker-compose up)
-    pub async fn up(&mut self, ui: &UI, verbose: bool) -> Result<()> {
-        ui.info("Starting container-compose services");
+// The Apple `container` CLI, looked up in PATH unless another binary is configured
+pub const DEFAULT_CONTAINER_BINARY: &str = "container";
 
-        // Initialize named volumes first
-        self.initialize_volumes().await?;
-        if verbose && !self.config.volumes.is_empty() {
-            ui.info(&format!(
-                "Initialized {} named volume(s)",
-                self.config.volumes.len()
-            ));
-        }
+// Lines of history `logs` shows when not following and no --tail is given
+pub const DEFAULT_LOG_TAIL: usize = 100;
 
-        // Get service start order based on dependencies
-        let start_order = self.get_start_order()?;
+// Seconds `up --wait` gives services to become healthy without a --wait-timeout
+pub const DEFAULT_WAIT_TIMEOUT: u64 = 60;
 
-        let mut started_count = 0;
-        for service_name in start_order {
-            self.start_service_with_progress(&service_name, ui, verbose)
-                .await?;
-            started_count += 1;
-        }
+// Healthcheck output lines shown for a service that never became healthy
+const HEALTH_OUTPUT_LINES: usize = 10;
 
-        ui.success(&format!("Started {} service(s)", started_count));
-        Ok(())
-    }
+// How often `up --wait` looks at services that are not healthy yet
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
-    // Stop all services (like docker-compose down)
-    pub async fn down(&mut self, ui: &UI, verbose: bool) -> Result<()> {
-        ui.info("Stopping container-compose services");
+// Log lines shown for a service that exits right after starting
+const FAILED_START_LOG_LINES: usize = 20;
 
-        // Get all containers that exist (running and stopped) for our services
-        let existing_containers = self.get_all_service_containers().await?;
+// How often a freshly started container is checked while confirming it stays up
+const LIVENESS_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
-        if existing_containers.is_empty() {
-            ui.info("No containers to stop");
-            return Ok(());
-        }
+// Labels tying a container to its project and service, used to find orphans
+const PROJECT_LABEL: &str = "container-compose.project";
+const SERVICE_LABEL: &str = "container-compose.service";
 
-        // Stop in reverse order - process all existing containers
-        let mut service_names: Vec<String> = self.config.services.keys().cloned().collect();
-        service_names.reverse();
-        service_names.retain(|name| existing_containers.contains(name));
+// Label marking a one-off `run` container, which is never one of its service's
+// instances nor an orphan
+const ONEOFF_LABEL: &str = "container-compose.oneoff";
 
-        for service_name in service_names {
-            self.stop_service_with_progress(&service_name, ui, verbose)
-                .await?;
-        }
+// Label holding the hash of the service definition a container was created from
+const CONFIG_HASH_LABEL: &str = "container-compose.config-hash";
 
-        ui.success(&format!(
-            "Processed {} service(s)",
-            existing_containers.len()
-        ));
-        Ok(())
-    }
+// Label holding when a container was created (Unix seconds), so `ps` can tell
+// a healthcheck still inside its start period from a failing one
+const CREATED_LABEL: &str = "container-compose.created";
 
-    // Start a specific service with progress bar
-    async fn start_service_with_progress(
-        &mut self,
-        service_name: &str,
-        ui: &UI,
-        verbose: bool,
-    ) -> Result<()> {
-        // Clone the service to avoid borrowing issues
-        let service = self
-            .config
-            .services
-            .get(service_name)
-            .ok_or_else(|| anyhow::anyhow!("Service '{}' not found", service_name))?
-            .clone();
+// How many healthchecks `ps` runs at once, and how long each may take, so a
+// hanging check can't hold up the listing for its full timeout
+const PS_HEALTH_PROBES: usize = 8;
+const PS_HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
 
-        // Check if service is already running
-        if let Some(container) = self.containers.get(service_name) {
-            if container.status == ContainerStatus::Running {
-                ui.inline_warning(&format!("{} already running", service_name));
-                return Ok(());
-            }
-        }
+// How `up` treats a service whose container already exists
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RecreatePolicy {
+    // Recreate only when the service definition changed
+    #[default]
+    Changed,
+    Always,
+    Never,
+}
 
-        // Create progress bar for starting
-        let pb = ui.create_start_progress(service_name);
+// Options for bringing services up
+#[derive(Debug, Clone)]
+pub struct UpOptions {
+    pub start_timeout: u64,
+    // Seconds to wait for every service to become healthy
+    pub wait_timeout: Option<u64>,
+    // Block until every service is healthy (implied by `wait_timeout`)
+    pub wait: bool,
+    // Seconds each container must stay running after start before `up` moves on
+    pub confirm_running: Option<u64>,
+    pub remove_orphans: bool,
+    pub recreate: RecreatePolicy,
+    // Number of containers to run per service, for services not at the default of one
+    pub scale: HashMap<String, usize>,
+    // Start every service that can be started instead of stopping at the first failure
+    pub keep_going: bool,
+}
 
-        if verbose {
-            println!(); // New line for verbose output
-            // Show port mapping warnings in verbose mode
-            if !service.ports.is_empty() {
-                ui.warning(
-                    "Port mappings are not directly supported by Apple's container framework",
-                );
-                ui.warning(&format!("Ports specified: {:?}", service.ports));
-                ui.warning("You may need to configure networking separately");
-            }
+impl Default for UpOptions {
+    fn default() -> Self {
+        Self {
+            start_timeout: DEFAULT_START_TIMEOUT,
+            wait_timeout: None,
+            wait: false,
+            confirm_running: None,
+            remove_orphans: false,
+            recreate: RecreatePolicy::default(),
+            scale: HashMap::new(),
+            keep_going: false,
         }
-
-        // Use Apple's container command to start the service
-        let container_id = self
-            .run_container_with_progress(service_name, &service, ui, verbose)
-            .await?;
-
-        // Finish progress bar and show result
-        pb.finish_and_clear();
-
-        let container = Container {
-            status: ContainerStatus::Running,
-            container_id: Some(container_id.clone()),
-        };
-
-        self.containers.insert(service_name.to_string(), container);
-        ui.inline_success(&format!("{} started ({})", service_name, container_id));
-
-        Ok(())
     }
+}
 
-    // Get the order to start services based on dependencies
-    fn get_start_order(&self) -> Result<Vec<String>> {
-        let mut order = Vec::new();
-        let mut visited = std::collections::HashSet::new();
-        let mut visiting = std::collections::HashSet::new();
+// Where a service stands while `up --wait` gates on it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Health {
+    Starting,
+    Healthy,
+    Unhealthy,
+    Exited,
+}
 
-        for service_name in self.config.services.keys() {
-            if !visited.contains(service_name) {
-                self.visit_service(service_name, &mut order, &mut visited, &mut visiting)?;
-            }
+impl Health {
+    fn label(self) -> &'static str {
+        match self {
+            Health::Starting => "starting",
+            Health::Healthy => "healthy",
+            Health::Unhealthy => "unhealthy",
+            Health::Exited => "exited",
         }
-
-        Ok(order)
     }
 
-    // Recursive function for topological sort (dependency resolution)
-    fn visit_service(
-        &self,
-        service_name: &str,
-        order: &mut Vec<String>,
-        visited: &mut std::collections::HashSet<String>,
-        visiting: &mut std::collections::HashSet<String>,
-    ) -> Result<()> {
-        if visiting.contains(service_name) {
-            return Err(anyhow::anyhow!(
-                "Circular dependency detected involving '{}'",
-                service_name
-            ));
+    fn color(self) -> &'static str {
+        match self {
+            Health::Healthy => "green",
+            Health::Starting => "yellow",
+            Health::Unhealthy | Health::Exited => "red",
         }
+    }
+}
 
-        if visited.contains(service_name) {
-            return Ok(());
-        }
+// A service's healthcheck history during `up --wait`
+#[derive(Debug, Clone)]
+struct HealthProgress {
+    health: Health,
+    failures: u32,
+    next_check: Instant,
+    last_output: String,
+}
 
-        visiting.insert(service_name.to_string());
+// Which service each of our container names belongs to, built once per
+// operation rather than formatting every service's names per container
+struct ContainerIndex<'a> {
+    // `container_name` or `<project>-<service>`
+    exact: HashMap<String, &'a String>,
+    // `<project>-<service>`, for replicas named `<project>-<service>-<index>`
+    replicas: HashMap<String, &'a String>,
+}
 
-        if let Some(service) = self.config.services.get(service_name) {
-            for dep in &service.depends_on {
-                self.visit_service(dep, order, visited, visiting)?;
-            }
+impl ContainerIndex<'_> {
+    // The service a container belongs to, if it is one of ours. Exact names win
+    // over replica names, so a service called `web-1` is never mistaken for a
+    // replica of `web`.
+    fn service(&self, container: &str) -> Option<&String> {
+        if let Some(service) = self.exact.get(container) {
+            return Some(service);
         }
-
-        visiting.remove(service_name);
-        visited.insert(service_name.to_string());
-        order.push(service_name.to_string());
-
-        Ok(())
+        let (prefix, index) = container.rsplit_once('-')?;
+        index.parse::<usize>().ok()?;
+        self.replicas.get(prefix).copied()
     }
+}
 
-    // Get logs from a service
-    pub async fn logs(&self, service_name: &str, follow: bool) -> Result<()> {
-        if let Some(container) = self.containers.get(service_name) {
-            if let Some(container_id) = &container.container_id {
-                let mut cmd = AsyncCommand::new("container");
-                cmd.args(&["logs"]);
+// A container labeled as belonging to this project
+#[derive(Debug, Clone)]
+struct ProjectContainer {
+    id: String,
+    service: String,
+    config_hash: Option<String>,
+    running: bool,
+    created: Option<u64>,
+}
 
-                if follow {
-                    cmd.arg("-f");
-                }
+// Options for stopping services with `down`, `stop` and `kill`
+#[derive(Debug, Clone)]
+pub struct StopOptions {
+    // Seconds to wait for a graceful stop before killing (0 kills right away)
+    pub timeout: u64,
+    pub keep_going: bool,
+    // Also stop and remove containers of services no longer in the config
+    pub remove_orphans: bool,
+    // Cap on the services `down` stops at once (unlimited when unset)
+    pub parallel: Option<NonZeroUsize>,
+}
 
-                cmd.arg(container_id);
-                cmd.stdout(Stdio::inherit());
-                cmd.stderr(Stdio::inherit());
+impl Default for StopOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_STOP_TIMEOUT,
+            keep_going: false,
+            remove_orphans: false,
+            parallel: None,
+        }
+    }
+}
 
-                let status = cmd.status().await?;
+// Options for the log view; `tail: None` shows the whole history
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    pub follow: bool,
+    // Lines to show from the end of each container's log
+    pub tail: Option<usize>,
+    pub no_color: bool,
+    pub no_log_prefix: bool,
+    // Only print lines containing this text, matched without the service prefix
+    pub grep: Option<String>,
+}
 
-                if !status.success() {
-                    return Err(anyhow::anyhow!(
-                        "Failed to get logs for service '{}'",
-                        service_name
-                    ));
-                }
-            } else {
-                return Err(anyhow::anyhow!("Service '{}' is not running", service_name));
-            }
-        } else {
-            return Err(anyhow::anyhow!("Service '{}' not found", service_name));
-        }
+// Options for building service images
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    pub no_cache: bool,
+    // Always try to pull a newer base image first
+    pub pull: bool,
+}
 
-        Ok(())
-    }
+// Options for running a command in a service container
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    pub workdir: Option<String>,
+    pub interactive: bool,
+    pub tty: bool,
+}
 
-    // Pull images for services
-    pub async fn pull(&self, service_name: Option<String>, ui: &UI, verbose: bool) -> Result<()> {
-        let services_to_pull = if let Some(name) = service_name {
-            // Pull specific service
-            if let Some(service) = self.config.services.get(&name) {
-                vec![(name, service)]
-            } else {
-                return Err(anyhow::anyhow!("Service '{}' not found", name));
-            }
-        } else {
-            // Pull all services
-            self.config
-                .services
-                .iter()
-                .map(|(k, v)| (k.clone(), v))
-                .collect()
-        };
+// Overrides for a one-off `run`, layered on top of the service's definition
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub workdir: Option<String>,
+    pub user: Option<String>,
+    // `KEY=VALUE` entries, each replacing the service's own value for KEY
+    pub env: Vec<String>,
+    pub no_tty: bool,
+    // Publish the service's ports, which one-off runs leave alone so they
+    // don't collide with an instance started by `up`
+    pub service_ports: bool,
+    pub rm: bool,
+}
 
-        for (name, service) in services_to_pull {
-            ui.info(&format!("Pulling image for service '{}'", name));
-            self.pull_image(&service.image, ui, verbose).await?;
-        }
+// Options for listing services with `ps`
+#[derive(Debug, Clone, Default)]
+pub struct PsOptions {
+    pub format: Option<String>,
+    pub services: bool,
+    pub quiet: bool,
+    pub filter: Vec<String>,
+    // Show how often each container restarted, flagging counts above this
+    pub restarts: Option<u32>,
+    // List the containers `--remove-orphans` would remove instead of the services
+    pub orphans: bool,
+}
 
-        ui.success("All images pulled successfully");
-        Ok(())
+// One line of `ps` output
+#[derive(Debug, Default)]
+struct PsRow {
+    service: String,
+    state: ServiceState,
+    status: String,
+    status_color: &'static str,
+    container_id: String,
+    image: String,
+    ports: String,
+    restarts: String,
+}
+
+impl PsRow {
+    // Values available to `ps --format` templates
+    fn fields(&self) -> [(&'static str, &str); 6] {
+        [
+            ("Service", &self.service),
+            ("Status", &self.status),
+            ("ContainerID", &self.container_id),
+            ("Image", &self.image),
+            ("Ports", &self.ports),
+            ("Restarts", &self.restarts),
+        ]
     }
+}
 
-    // Pull a specific image
-    async fn pull_image(&self, image: &str, ui: &UI, verbose: bool) -> Result<()> {
-        let mut cmd = AsyncCommand::new("container");
-        cmd.args(&["images", "pull", image]);
+// How often `events` polls the container framework for changes
+const EVENTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
-        if verbose {
-            ui.command(&format!("container images pull {}", image));
-        }
+// Where a service's container is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum ServiceState {
+    #[default]
+    Absent,
+    Created,
+    Running,
+}
 
-        // Create progress bar
-        let pb = ui.create_pull_progress(image);
+// Where a service is in the depth-first walk that orders services
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VisitState {
+    New,
+    Visiting,
+    Visited,
+}
 
-        let output = cmd.output().await?;
+// A lifecycle change reported by `events`
+#[derive(Debug, Serialize)]
+struct ServiceEvent {
+    time: u64,
+    service: String,
+    action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+}
 
-        pb.finish_and_clear();
+// Main container manager
+pub struct ContainerManager {
+    containers: HashMap<String, Container>,
+    config: ContainerComposeConfig,
+    project_name: String,
+    project_dir: PathBuf,
+    volumes_dir: Option<PathBuf>,
+    // The `container` executable every command shells out to
+    binary: PathBuf,
+}
 
-        if output.status.success() {
-            ui.success(&format!("Successfully pulled: {}", image));
-            // Print any output from the pull command if verbose
-            if verbose && !output.stdout.is_empty() {
-                println!("{}", String::from_utf8_lossy(&output.stdout));
+impl ContainerManager {
+    pub fn new(
+        config: ContainerComposeConfig,
+        project_name: String,
+        project_dir: PathBuf,
+        volumes_dir: Option<PathBuf>,
+        binary: PathBuf,
+    ) -> Self {
+        // Services with only a build section run the image it produces
+        let mut config = config;
+        for (name, service) in config.services.iter_mut() {
+            if service.image.is_empty() && service.build.is_some() {
+                service.image = default_image(&project_name, name);
             }
-        } else {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!(
-                "Failed to pull image '{}': {}",
-                image,
-                error_msg
-            ));
         }
 
-        Ok(())
+        Self {
+            containers: HashMap::new(),
+            config,
+            project_name,
+            project_dir,
+            volumes_dir,
+            binary,
+        }
     }
 
-    // Run a container with progress (used by start_service_with_progress)
-    async fn run_container_with_progress(
-        &self,
-        name: &str,
-        service: &Service,
-        ui: &UI,
-        verbose: bool,
-    ) -> Result<String> {
-        let mut cmd = AsyncCommand::new("container");
-        cmd.args(&["run", "--detach", "--name", name]);
-
-        // Add volume mounts (handle both bind mounts and named volumes)
-        for volume in &service.volumes {
-            let volume_spec = self.process_volume_mount(volume)?;
-            cmd.args(&["--volume", &volume_spec]);
-        }
+    // Whether a service's image only comes from its build section, so there's
+    // nothing to pull and `up` builds it when missing
+    fn builds_locally(&self, service_name: &str) -> bool {
+        let service = &self.config.services[service_name];
+        service.build.is_some() && service.image == default_image(&self.project_name, service_name)
+    }
 
-        // Add environment variables
-        for env in &service.environment {
-            cmd.args(&["--env", env]);
-        }
+    // Start building an invocation of the configured `container` binary
+    fn container_command(&self) -> AsyncCommand {
+        AsyncCommand::new(&self.binary)
+    }
 
-        // Set working directory if specified
-        if let Some(working_dir) = &service.working_dir {
-            cmd.args(&["--workdir", working_dir]);
-        }
+    // Name of the container backing a service: its explicit `container_name`,
+    // or `<project>-<service>`. Everything that shells out for a service goes
+    // through here so the naming scheme lives in one place.
+    pub fn container_name(&self, service_name: &str) -> String {
+        self.config
+            .services
+            .get(service_name)
+            .and_then(|service| service.container_name.clone())
+            .unwrap_or_else(|| format!("{}-{}", self.project_name, service_name))
+    }
 
-        // Add the image
-        cmd.arg(&service.image);
+    // Name of one replica of a scaled service: `<project>-<service>-<index>`
+    fn replica_name(&self, service_name: &str, index: usize) -> String {
+        format!("{}-{}-{}", self.project_name, service_name, index)
+    }
 
-        // Add command if specified
-        if let Some(command) = &service.command {
-            cmd.args(command);
+    // Containers a service should run: its usual container, or numbered replicas when scaled
+    fn instance_names(&self, service_name: &str, replicas: usize) -> Vec<String> {
+        if replicas == 1 {
+            vec![self.container_name(service_name)]
+        } else {
+            (1..=replicas)
+                .map(|index| self.replica_name(service_name, index))
+                .collect()
         }
+    }
 
-        if verbose {
-            ui.command(&format!("{:?}", cmd));
+    // Map our container names back to their services
+    fn container_index(&self) -> ContainerIndex<'_> {
+        let mut index = ContainerIndex {
+            exact: HashMap::new(),
+            replicas: HashMap::new(),
+        };
+        for (service_name, service) in &self.config.services {
+            index
+                .exact
+                .insert(self.container_name(service_name), service_name);
+            if service.container_name.is_none() {
+                index.replicas.insert(
+                    format!("{}-{}", self.project_name, service_name),
+                    service_name,
+                );
+            }
         }
+        index
+    }
 
-        let output = cmd.output().await?;
-
-        if output.status.success() {
-            let container_id = String::from_utf8(output.stdout)?.trim().to_string();
-            Ok(container_id)
+    // How a container is referred to in output: the service name, or `<service>-<index>`
+    // for a replica of a scaled service
+    fn instance_label(&self, service_name: &str, container: &str) -> String {
+        if self.container_name(service_name) == container {
+            service_name.to_string()
         } else {
-            Err(anyhow::anyhow!(
-                "Failed to start container '{}': {}",
-                name,
-                String::from_utf8_lossy(&output.stderr)
-            ))
+            container
+                .strip_prefix(&format!("{}-", self.project_name))
+                .unwrap_or(container)
+                .to_string()
         }
     }
 
-    // Get list of running containers
-    async fn get_running_containers(&self) -> Result<Vec<String>> {
-        let output = AsyncCommand::new("container")
-            .args(&["list"])
-            .output()
-            .await?;
-
-        if output.status.success() {
-            let container_list = String::from_utf8_lossy(&output.stdout);
-            let running_containers: Vec<String> = container_list
-                .lines()
-                .skip(1) // Skip header line
-                .filter(|line| !line.is_empty())
-                .filter_map(|line| {
-                    // Parse the first column (ID/name) from container list
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() > 0 {
-                        let container_name = parts[0];
-                        // Only include if it's one of our services
-                        if self.config.services.contains_key(container_name) {
-                            Some(container_name.to_string())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            Ok(running_containers)
+    // The existing containers of a service among `instances`, or its usual container
+    // when none exist, so commands on it still surface the framework's own error
+    fn containers_of(&self, service_name: &str, instances: &[(String, String)]) -> Vec<String> {
+        let containers: Vec<String> = instances
+            .iter()
+            .filter(|(service, _)| service == service_name)
+            .map(|(_, container)| container.clone())
+            .collect();
+        if containers.is_empty() {
+            vec![self.container_name(service_name)]
         } else {
-            Ok(Vec::new())
+            containers
         }
     }
 
-    // Get list of all containers (running and stopped) for our services
-    async fn get_all_service_containers(&self) -> Result<Vec<String>> {
-        let output = AsyncCommand::new("container")
-            .args(&["list", "--all"]) // Include stopped containers
-            .output()
-            .await?;
-
-        if output.status.success() {
-            let container_list = String::from_utf8_lossy(&output.stdout);
-            let all_containers: Vec<String> = container_list
-                .lines()
-                .skip(1) // Skip header line
-                .filter(|line| !line.is_empty())
-                .filter_map(|line| {
-                    // Parse the first column (ID/name) from container list
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() > 0 {
-                        let container_name = parts[0];
-                        // Only include if it's one of our services
-                        if self.config.services.contains_key(container_name) {
-                            Some(container_name.to_string())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
+    // Reject `--scale` requests that can't be satisfied before anything is started
+    fn check_scale(&self, scale: &HashMap<String, usize>) -> Result<()> {
+        let mut requests: Vec<(&String, &usize)> = scale.iter().collect();
+        requests.sort();
+
+        for (service_name, &replicas) in requests {
+            let Some(service) = self.config.services.get(service_name) else {
+                return Err(ComposeError::ServiceNotFound(service_name.clone()));
+            };
+            if replicas <= 1 {
+                continue;
+            }
+            if service.container_name.is_some() {
+                return Err(ComposeError::InvalidScale {
+                    service: service_name.clone(),
+                    replicas,
+                    reason: "it sets container_name, which only one container can have".to_string(),
+                });
+            }
+            let published: Vec<&str> = service
+                .ports
+                .iter()
+                .filter(|port| port.contains(':'))
+                .map(String::as_str)
                 .collect();
-
-            Ok(all_containers)
-        } else {
-            Ok(Vec::new())
+            if !published.is_empty() {
+                return Err(ComposeError::InvalidScale {
+                    service: service_name.clone(),
+                    replicas,
+                    reason: format!(
+                        "it publishes host port(s) {}, which every replica would try to bind",
+                        published.join(", ")
+                    ),
+                });
+            }
         }
+        Ok(())
     }
 
-    // Stop a service with progress bar
-    async fn stop_service_with_progress(
+    // Start all services (like docker-compose up)
+    pub async fn up(
         &mut self,
-        service_name: &str,
-        ui: &UI,
+        options: &UpOptions,
+        ui: &dyn Reporter,
         verbose: bool,
     ) -> Result<()> {
-        // Create progress bar for stopping
-        let pb = ui.create_stop_progress(service_name);
+        self.check_scale(&options.scale)?;
+        ui.info("Starting container-compose services");
 
-        if verbose {
-            println!(); // New line for verbose output
-            ui.command(&format!("container stop {}", service_name));
+        // Check every mount and create named volumes before anything starts
+        self.initialize_volumes(ui).await?;
+        let mounted = self.config.mounted_volumes().len();
+        if verbose && mounted > 0 {
+            ui.info(&format!("Initialized {} named volume(s)", mounted));
         }
 
-        // Try to stop the container gracefully first with timeout
-        let stop_result = timeout(
-            Duration::from_secs(10),
-            AsyncCommand::new("container")
-                .args(&["stop", service_name])
-                .output(),
-        )
-        .await;
-
-        let mut output = match stop_result {
-            Ok(result) => result?,
-            Err(_) => {
-                // Timeout - container is not responding, force kill
-                if verbose {
-                    ui.command(&format!("container kill {} (timeout)", service_name));
-                }
-                AsyncCommand::new("container")
-                    .args(&["kill", service_name])
-                    .output()
-                    .await?
-            }
-        };
+        // Create the networks services attach to
+        self.initialize_networks(ui).await?;
 
-        // If graceful stop failed, try force kill
-        if !output.status.success() {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            if !error_msg.contains("no such container") && !error_msg.contains("not found") {
-                if verbose {
-                    ui.command(&format!("container kill {}", service_name));
-                }
+        // `${VAR}` references resolve against the environment as it is now
+        let host_env: HashMap<String, String> = std::env::vars().collect();
 
-                // Try force kill
-                output = AsyncCommand::new("container")
-                    .args(&["kill", service_name])
-                    .output()
+        // Build the images of build-only services that don't exist yet
+        for name in self.get_start_order()? {
+            if !self.builds_locally(&name)
+                || Self::resolve_image_digest(&self.binary, &self.config.services[&name].image)
+                    .await
+                    .is_some()
+            {
+                continue;
+            }
+            let service = self.config.services[&name].interpolated(&name, &host_env)?;
+            if let Some(build) = &service.build {
+                ui.info(&format!("Building image for service '{}'", name));
+                self.build_image(&name, &service.image, build, &BuildOptions::default(), ui)
                     .await?;
-
-                // If kill also failed, try one more time after a brief delay
-                if !output.status.success() && verbose {
-                    ui.command(&format!("container kill {} (retry)", service_name));
-                    tokio::time::sleep(Duration::from_millis(500)).await;
-                    output = AsyncCommand::new("container")
-                        .args(&["kill", service_name])
-                        .output()
-                        .await?;
-                }
             }
         }
 
-        // Finish progress bar and show result
-        pb.finish_and_clear();
+        // Containers left behind by renamed or removed services
+        self.handle_orphans(options.remove_orphans, DEFAULT_STOP_TIMEOUT, ui, verbose)
+            .await?;
 
-        if output.status.success() {
-            ui.inline_success(&format!("{} stopped", service_name));
+        // Get service start order based on dependencies
+        let start_order = self.get_start_order()?;
 
-            // Also try to remove the container
-            if verbose {
-                ui.command(&format!("container rm {}", service_name));
+        // Existing containers are kept when their definition is unchanged
+        let labeled = self.get_project_containers().await?;
+        let existing = self.get_service_instances().await?;
+        let running = self.get_container_names(false).await?;
+
+        // With `keep_going`, a failed service doesn't stop the rest; services
+        // depending on it are skipped, and every failure is reported at the end
+        let mut started_count = 0;
+        let mut failures: Vec<(String, ComposeError)> = Vec::new();
+        for service_name in start_order {
+            let failed_dependency = self.config.services[&service_name]
+                .depends_on
+                .iter()
+                .find(|dependency| failures.iter().any(|(failed, _)| failed == *dependency))
+                .cloned();
+            if let Some(dependency) = failed_dependency {
+                ui.service_event(
+                    &service_name,
+                    ServiceAction::Failed {
+                        error: format!("dependency '{}' failed", dependency),
+                    },
+                );
+                failures.push((service_name, ComposeError::DependencyFailed(dependency)));
+                continue;
+            }
+
+            let result =
+                match self.config.services[&service_name].interpolated(&service_name, &host_env) {
+                    Ok(service) => {
+                        self.up_service(
+                            &service_name,
+                            &service,
+                            options,
+                            &existing,
+                            &labeled,
+                            &running,
+                            ui,
+                            verbose,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+            match result {
+                Ok(started) => started_count += started,
+                Err(e) if options.keep_going => failures.push((service_name, e)),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !failures.is_empty() {
+            ui.warning(&format!(
+                "Started {} service(s), {} failed",
+                started_count,
+                failures.len()
+            ));
+            return Err(ComposeError::ServicesFailed {
+                action: "start".to_string(),
+                failures,
+            });
+        }
+        ui.success(&format!("Started {} service(s)", started_count));
+
+        if options.wait || options.wait_timeout.is_some() {
+            let wait_timeout = options.wait_timeout.unwrap_or(DEFAULT_WAIT_TIMEOUT);
+            self.wait_until_healthy(wait_timeout, ui).await?;
+        }
+
+        Ok(())
+    }
+
+    // Bring one service's containers in line with `options`, returning how many
+    // are up afterwards; `service` is its definition with `${VAR}` resolved
+    #[allow(clippy::too_many_arguments)]
+    async fn up_service(
+        &mut self,
+        service_name: &str,
+        service: &Service,
+        options: &UpOptions,
+        existing: &[(String, String)],
+        labeled: &[ProjectContainer],
+        running: &[String],
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<usize> {
+        let mut started_count = 0;
+        let replicas = options.scale.get(service_name).copied().unwrap_or(1);
+        let instances = self.instance_names(service_name, replicas);
+
+        // Containers left over from a different scale
+        for (_, container) in existing
+            .iter()
+            .filter(|(s, c)| *s == service_name && !instances.contains(c))
+        {
+            let label = self.instance_label(service_name, container);
+            self.stop_container_with_progress(
+                &label,
+                container,
+                DEFAULT_STOP_TIMEOUT,
+                true,
+                ui,
+                verbose,
+            )
+            .await?;
+        }
+
+        let wanted_hash = service.config_hash();
+        for container in &instances {
+            let label = self.instance_label(service_name, container);
+
+            if existing.iter().any(|(_, c)| c == container) {
+                let current_hash = labeled
+                    .iter()
+                    .find(|c| &c.id == container)
+                    .and_then(|c| c.config_hash.as_ref());
+                let changed = current_hash != Some(&wanted_hash);
+
+                let recreate = match options.recreate {
+                    RecreatePolicy::Always => true,
+                    RecreatePolicy::Never => false,
+                    RecreatePolicy::Changed => changed,
+                };
+
+                if !recreate {
+                    let is_running = running.contains(container);
+                    self.keep_service(service_name, container, is_running, changed, ui)
+                        .await?;
+                    started_count += 1;
+                    continue;
+                }
+
+                if changed {
+                    ui.inline_info(&format!("{} definition changed, recreating", label));
+                }
+                self.stop_container_with_progress(
+                    &label,
+                    container,
+                    DEFAULT_STOP_TIMEOUT,
+                    true,
+                    ui,
+                    verbose,
+                )
+                .await?;
+            }
+
+            self.start_service_with_progress(
+                service_name,
+                service,
+                container,
+                options,
+                ui,
+                verbose,
+            )
+            .await?;
+            started_count += 1;
+        }
+
+        Ok(started_count)
+    }
+
+    // Block until every started container passes its service's healthcheck (or,
+    // without one, is running), printing a health table and failing if that
+    // doesn't happen within `wait_timeout` seconds. A container that exits fails
+    // the wait at once, with its last log lines.
+    async fn wait_until_healthy(&self, wait_timeout: u64, ui: &dyn Reporter) -> Result<()> {
+        let mut containers: Vec<&String> = self.containers.keys().collect();
+        containers.sort();
+        let labels: HashMap<&String, String> = containers
+            .iter()
+            .map(|c| (*c, self.instance_label(&self.containers[*c].service, c)))
+            .collect();
+
+        ui.info(&format!(
+            "Waiting up to {}s for {} container(s) to become healthy",
+            wait_timeout,
+            containers.len()
+        ));
+
+        let started = Instant::now();
+        let deadline = started + Duration::from_secs(wait_timeout);
+        let mut progress: HashMap<&String, HealthProgress> = containers
+            .iter()
+            .map(|name| {
+                let entry = HealthProgress {
+                    health: Health::Starting,
+                    failures: 0,
+                    next_check: started,
+                    last_output: String::new(),
+                };
+                (*name, entry)
+            })
+            .collect();
+
+        loop {
+            let running = self.get_container_names(false).await?;
+
+            for name in &containers {
+                let entry = progress.get_mut(name).expect("every container is tracked");
+                if entry.health != Health::Starting {
+                    continue;
+                }
+
+                // A crashed container will never become healthy, so notice it on
+                // every poll rather than once its next healthcheck is due
+                if !running.contains(name) {
+                    entry.health = Health::Exited;
+                    continue;
+                }
+
+                if Instant::now() < entry.next_check {
+                    continue;
+                }
+
+                let service = &self.containers[*name].service;
+                let Some(check) = &self.config.services[service].healthcheck else {
+                    entry.health = Health::Healthy;
+                    continue;
+                };
+                let Some(command) = check.command() else {
+                    entry.health = Health::Healthy;
+                    continue;
+                };
+
+                let (passed, output) = self
+                    .run_healthcheck(name, &command, check.timeout(), ui)
+                    .await;
+                entry.last_output = output;
+                entry.next_check = Instant::now() + check.interval();
+
+                if passed {
+                    entry.health = Health::Healthy;
+                } else if started.elapsed() >= check.start_period() {
+                    entry.failures += 1;
+                    if entry.failures >= check.retries() {
+                        entry.health = Health::Unhealthy;
+                    }
+                }
+            }
+
+            let failed: Vec<&str> = containers
+                .iter()
+                .filter(|name| progress[*name].health == Health::Unhealthy)
+                .map(|name| labels[*name].as_str())
+                .collect();
+            let pending: Vec<&str> = containers
+                .iter()
+                .filter(|name| progress[*name].health == Health::Starting)
+                .map(|name| labels[*name].as_str())
+                .collect();
+
+            let exited: Vec<&String> = containers
+                .iter()
+                .copied()
+                .filter(|name| progress[*name].health == Health::Exited)
+                .collect();
+            if !exited.is_empty() {
+                // Fail right away, with the logs that say why it exited
+                self.print_health_report(&containers, &labels, &progress, ui);
+                let mut error = None;
+                for name in exited {
+                    let exit = self.report_exit(&labels[name], name, ui).await;
+                    error.get_or_insert(exit);
+                }
+                return Err(error.expect("at least one container exited"));
+            }
+            if !failed.is_empty() {
+                self.print_health_report(&containers, &labels, &progress, ui);
+                return Err(ComposeError::Unhealthy(
+                    failed.iter().map(|s| s.to_string()).collect(),
+                ));
+            }
+            if pending.is_empty() {
+                ui.success("All services are healthy");
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                self.print_health_report(&containers, &labels, &progress, ui);
+                return Err(ComposeError::HealthTimeout {
+                    services: pending.iter().map(|s| s.to_string()).collect(),
+                    seconds: wait_timeout,
+                });
+            }
+
+            tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+        }
+    }
+
+    // Run a healthcheck command in a container, returning whether it passed and
+    // what it printed
+    async fn run_healthcheck(
+        &self,
+        container: &str,
+        command: &[String],
+        check_timeout: Duration,
+        ui: &dyn Reporter,
+    ) -> (bool, String) {
+        let mut cmd = self.container_command();
+        cmd.arg("exec").arg(container);
+        cmd.args(command);
+        cmd.kill_on_drop(true);
+
+        match timeout(check_timeout, run_logged(&mut cmd, ui)).await {
+            Ok(Ok(output)) => {
+                let text = format!(
+                    "{}{}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                (output.status.success(), text.trim().to_string())
+            }
+            Ok(Err(e)) => (false, e.to_string()),
+            Err(_) => (
+                false,
+                format!("healthcheck timed out after {:?}", check_timeout),
+            ),
+        }
+    }
+
+    // Print each container's final health, plus the last healthcheck output of
+    // any that isn't healthy
+    fn print_health_report(
+        &self,
+        containers: &[&String],
+        labels: &HashMap<&String, String>,
+        progress: &HashMap<&String, HealthProgress>,
+        ui: &dyn Reporter,
+    ) {
+        ui.separator();
+        ui.table_header(&["SERVICE", "HEALTH"]);
+        for name in containers {
+            let health = progress[*name].health;
+            ui.table_row(&[&labels[*name], health.label()], Some(health.color()));
+        }
+
+        for name in containers {
+            let entry = &progress[*name];
+            if entry.health == Health::Healthy || entry.last_output.is_empty() {
+                continue;
+            }
+            let label = &labels[*name];
+            ui.warning(&format!("{} last healthcheck output:", label));
+            let lines: Vec<&str> = entry.last_output.lines().collect();
+            let start = lines.len().saturating_sub(HEALTH_OUTPUT_LINES);
+            for line in &lines[start..] {
+                ui.log_line(label, label.len(), line, true);
+            }
+        }
+    }
+
+    // Stay attached to the started services, streaming their logs until Ctrl-C,
+    // which detaches and leaves them running. With `abort_on_container_exit`,
+    // the project is torn down as soon as any service exits and that service's
+    // exit code is returned; with `exit_code_from`, only that service exiting
+    // tears it down.
+    pub async fn attach(
+        &mut self,
+        abort_on_container_exit: bool,
+        exit_code_from: Option<&str>,
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<Option<i32>> {
+        let mut containers: Vec<String> = self.containers.keys().cloned().collect();
+        containers.sort();
+
+        ui.info("Attaching to service logs (press Ctrl-C to stop)");
+
+        let log_options = LogOptions {
+            follow: true,
+            ..Default::default()
+        };
+        // Tear down everything that will stop, even if one container won't
+        let teardown = StopOptions {
+            keep_going: true,
+            ..Default::default()
+        };
+
+        // With `exit_code_from`, only that service exiting ends the run, so its
+        // exit code is final when read
+        let watched: Vec<String> = match exit_code_from {
+            Some(reported) => containers
+                .iter()
+                .filter(|c| self.containers[*c].service == reported)
+                .cloned()
+                .collect(),
+            None => containers.clone(),
+        };
+
+        // Exits are still watched for once every log stream has ended
+        let streamed = async {
+            self.logs(None, &log_options, ui).await?;
+            if abort_on_container_exit {
+                std::future::pending::<()>().await;
+            }
+            Ok::<(), ComposeError>(())
+        };
+
+        let exited = tokio::select! {
+            result = streamed => {
+                result?;
+                None
+            }
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                None
+            }
+            container = self.wait_for_exit(&watched), if abort_on_container_exit => Some(container?),
+        };
+
+        // Ctrl-C or the end of the logs only detaches; the services keep running
+        let Some(exited_container) = exited else {
+            ui.separator();
+            ui.info("Detached; services are still running (`down` stops them)");
+            return Ok(None);
+        };
+
+        let service_name = self.containers[&exited_container].service.clone();
+        let exited_code = self
+            .container_exit_code(&exited_container)
+            .await
+            .unwrap_or(1);
+        ui.separator();
+        ui.warning(&format!(
+            "{} exited with code {}, aborting",
+            self.instance_label(&service_name, &exited_container),
+            exited_code
+        ));
+
+        self.down(&teardown, ui, verbose).await?;
+
+        Ok(Some(exited_code))
+    }
+
+    // Poll until one of the given containers is no longer running, returning its name
+    async fn wait_for_exit(&self, containers: &[String]) -> Result<String> {
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            let running = self.get_container_names(false).await?;
+            if let Some(container) = containers.iter().find(|c| !running.contains(c)) {
+                return Ok(container.clone());
+            }
+        }
+    }
+
+    // Read a container's exit code from `container inspect`
+    async fn container_exit_code(&self, container: &str) -> Option<i32> {
+        let output = self
+            .container_command()
+            .args(["inspect", container])
+            .output()
+            .await
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+        find_json_key(&info, "exitCode")?
+            .as_i64()
+            .map(|code| code as i32)
+    }
+
+    // How many times the framework restarted a container, 0 when it doesn't say
+    async fn container_restart_count(&self, container: &str) -> u32 {
+        let Ok(output) = self
+            .container_command()
+            .args(["inspect", container])
+            .output()
+            .await
+        else {
+            return 0;
+        };
+        if !output.status.success() {
+            return 0;
+        }
+
+        serde_json::from_slice::<serde_json::Value>(&output.stdout)
+            .ok()
+            .and_then(|info| {
+                find_json_key(&info, "restartCount")
+                    .or_else(|| find_json_key(&info, "RestartCount"))
+                    .and_then(serde_json::Value::as_u64)
+            })
+            .map_or(0, |count| count as u32)
+    }
+
+    // Stop all services (like docker-compose down)
+    pub async fn down(
+        &mut self,
+        options: &StopOptions,
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<()> {
+        ui.info("Stopping container-compose services");
+        self.handle_orphans(options.remove_orphans, options.timeout, ui, verbose)
+            .await?;
+
+        // Get all containers that exist (running and stopped) for our services
+        let existing_containers = self.get_service_instances().await?;
+
+        if existing_containers.is_empty() {
+            ui.info("No containers to stop");
+            return Ok(());
+        }
+
+        // Stop level by level in reverse start order, so dependents are gone before
+        // their dependencies; services within a level are stopped concurrently.
+        // A failure ends `down` after its level unless `keep_going`.
+        let limit = options.parallel.map_or(usize::MAX, NonZeroUsize::get);
+        let mut failures: Vec<(String, ComposeError)> = Vec::new();
+        for level in self.get_start_levels()?.into_iter().rev() {
+            let level: Vec<(String, String)> = existing_containers
+                .iter()
+                .filter(|(service, _)| level.contains(service))
+                .map(|(service, container)| {
+                    (self.instance_label(service, container), container.clone())
+                })
+                .collect();
+            let manager = &*self;
+            let results: Vec<(String, Result<()>)> = stream::iter(&level)
+                .map(|(name, container)| async move {
+                    let result = manager
+                        .stop_container_with_progress(
+                            name,
+                            container,
+                            options.timeout,
+                            true,
+                            ui,
+                            verbose,
+                        )
+                        .await;
+                    (name.clone(), result)
+                })
+                .buffer_unordered(limit)
+                .collect()
+                .await;
+            failures.extend(
+                results
+                    .into_iter()
+                    .filter_map(|(name, result)| result.err().map(|e| (name, e))),
+            );
+            if !options.keep_going
+                && let Some((_, e)) = failures.pop()
+            {
+                return Err(e);
+            }
+        }
+
+        if !failures.is_empty() {
+            ui.warning(&format!(
+                "Stopped {} container(s), {} failed",
+                existing_containers.len() - failures.len(),
+                failures.len()
+            ));
+            return Err(ComposeError::ServicesFailed {
+                action: "stop".to_string(),
+                failures,
+            });
+        }
+
+        ui.success(&format!(
+            "Processed {} container(s)",
+            existing_containers.len()
+        ));
+        Ok(())
+    }
+
+    // Stop running containers without removing them, dependents before their
+    // dependencies, or only the named service's. Stopping the whole project also
+    // takes care of orphans.
+    pub async fn stop(
+        &self,
+        service_name: Option<&str>,
+        options: &StopOptions,
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<()> {
+        if let Some(name) = service_name
+            && !self.config.services.contains_key(name)
+        {
+            return Err(ComposeError::ServiceNotFound(name.to_string()));
+        }
+        if service_name.is_none() {
+            self.handle_orphans(options.remove_orphans, options.timeout, ui, verbose)
+                .await?;
+        }
+
+        let instances = self.get_service_instances().await?;
+        let running = self.get_container_names(false).await?;
+
+        let mut stopped = 0;
+        let mut failures: Vec<(String, ComposeError)> = Vec::new();
+        for service in self.get_start_order()?.iter().rev() {
+            if service_name.is_some_and(|name| name != service) {
+                continue;
+            }
+            for container in self.containers_of(service, &instances) {
+                if !running.contains(&container) {
+                    continue;
+                }
+                let label = self.instance_label(service, &container);
+                match self
+                    .stop_container_with_progress(
+                        &label,
+                        &container,
+                        options.timeout,
+                        false,
+                        ui,
+                        verbose,
+                    )
+                    .await
+                {
+                    Ok(()) => stopped += 1,
+                    Err(e) if options.keep_going => failures.push((label, e)),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            ui.warning(&format!(
+                "Stopped {} container(s), {} failed",
+                stopped,
+                failures.len()
+            ));
+            return Err(ComposeError::ServicesFailed {
+                action: "stop".to_string(),
+                failures,
+            });
+        }
+
+        ui.success(&format!("Stopped {} container(s)", stopped));
+        Ok(())
+    }
+
+    // Start the existing, stopped containers of every service (or just the named
+    // one) in dependency order, without creating any; services never brought up
+    // are pointed out instead
+    pub async fn start(&self, service_name: Option<&str>, ui: &dyn Reporter) -> Result<()> {
+        if let Some(name) = service_name
+            && !self.config.services.contains_key(name)
+        {
+            return Err(ComposeError::ServiceNotFound(name.to_string()));
+        }
+
+        let instances = self.get_service_instances().await?;
+        let running = self.get_container_names(false).await?;
+
+        let mut started = 0;
+        let mut missing = Vec::new();
+        for service in self.get_start_order()? {
+            if service_name.is_some_and(|name| name != service) {
+                continue;
+            }
+            let containers: Vec<&String> = instances
+                .iter()
+                .filter(|(s, _)| *s == service)
+                .map(|(_, container)| container)
+                .collect();
+            if containers.is_empty() {
+                missing.push(service);
+                continue;
+            }
+            for container in containers {
+                if running.contains(container) {
+                    continue;
+                }
+                let label = self.instance_label(&service, container);
+                ui.service_event(&label, ServiceAction::Starting);
+                let output =
+                    run_logged(self.container_command().args(["start", container]), ui).await?;
+                if !output.status.success() {
+                    return Err(ComposeError::ContainerCommandFailed {
+                        action: "start existing container".to_string(),
+                        target: label,
+                        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                    });
+                }
+                ui.service_event(
+                    &label,
+                    ServiceAction::Started {
+                        container_id: container.clone(),
+                    },
+                );
+                started += 1;
+            }
+        }
+
+        if !missing.is_empty() {
+            ui.warning(&format!(
+                "No container to start for service(s): {}. Run `up` to create them",
+                missing.join(", ")
+            ));
+        }
+        ui.success(&format!("Started {} container(s)", started));
+        Ok(())
+    }
+
+    // Stop and remove the containers labeled for this project whose service is
+    // no longer in the config, or just point them out unless `remove`
+    async fn handle_orphans(
+        &self,
+        remove: bool,
+        stop_timeout: u64,
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<()> {
+        let orphans = self.get_orphans().await?;
+        if remove {
+            for orphan in &orphans {
+                self.stop_container_with_progress(
+                    &orphan.id,
+                    &orphan.id,
+                    stop_timeout,
+                    true,
+                    ui,
+                    verbose,
+                )
+                .await?;
+            }
+        } else if !orphans.is_empty() {
+            let names: Vec<String> = orphans
+                .iter()
+                .map(|o| format!("{} ({})", o.id, o.service))
+                .collect();
+            ui.warning(&format!(
+                "Found orphan container(s) for services no longer in the config: {}. Pass --remove-orphans to remove them",
+                names.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Leave an existing container in place, starting it again if it was stopped
+    async fn keep_service(
+        &mut self,
+        service_name: &str,
+        container: &str,
+        is_running: bool,
+        changed: bool,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let label = self.instance_label(service_name, container);
+
+        if !is_running {
+            ui.service_event(&label, ServiceAction::Starting);
+            let output =
+                run_logged(self.container_command().args(["start", container]), ui).await?;
+            if !output.status.success() {
+                return Err(ComposeError::ContainerCommandFailed {
+                    action: "start existing container".to_string(),
+                    target: label,
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                });
+            }
+        }
+
+        self.containers.insert(
+            container.to_string(),
+            Container {
+                service: service_name.to_string(),
+                status: ContainerStatus::Running,
+            },
+        );
+
+        match (changed, is_running) {
+            (true, _) => ui.inline_warning(&format!(
+                "{} definition changed but left as is (--no-recreate)",
+                label
+            )),
+            (false, true) => ui.inline_info(&format!("{} up to date", label)),
+            (false, false) => ui.service_event(
+                &label,
+                ServiceAction::Started {
+                    container_id: container.to_string(),
+                },
+            ),
+        }
+        Ok(())
+    }
+
+    // Start one container of a service with progress bar, from its definition
+    // with `${VAR}` already resolved
+    async fn start_service_with_progress(
+        &mut self,
+        service_name: &str,
+        service: &Service,
+        container: &str,
+        options: &UpOptions,
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<()> {
+        let label = self.instance_label(service_name, container);
+
+        // Check if the container is already running (a let-chain, as clippy's
+        // collapsible_if rejects the nested form under edition 2024)
+        if let Some(existing) = self.containers.get(container)
+            && existing.status == ContainerStatus::Running
+        {
+            ui.inline_warning(&format!("{} already running", label));
+            return Ok(());
+        }
+
+        // Create progress bar for starting
+        ui.service_event(&label, ServiceAction::Starting);
+        let pb = ui.create_start_progress(&label);
+
+        if verbose {
+            // Show port mapping warnings in verbose mode
+            if !service.ports.is_empty() {
+                ui.warning(
+                    "Port mappings are not directly supported by Apple's container framework",
+                );
+                ui.warning(&format!("Ports specified: {:?}", service.ports));
+                ui.warning("You may need to configure networking separately");
+            }
+        }
+
+        // Use Apple's container command to start the service, bounded so a hung run can't stall `up`
+        let start_timeout = service.start_timeout.unwrap_or(options.start_timeout);
+        let run_result = timeout(
+            Duration::from_secs(start_timeout),
+            self.run_container_with_progress(service_name, container, service, ui),
+        )
+        .await;
+
+        let failed = Container {
+            service: service_name.to_string(),
+            status: ContainerStatus::Failed,
+        };
+
+        let run_result = match run_result {
+            Ok(result) => result,
+            Err(_) => {
+                self.containers
+                    .insert(container.to_string(), failed.clone());
+                Err(ComposeError::StartTimeout {
+                    service: label.clone(),
+                    seconds: start_timeout,
+                })
+            }
+        };
+        let container_id = match run_result {
+            Ok(container_id) => container_id,
+            Err(e) => {
+                pb.finish_and_clear();
+                ui.service_event(
+                    &label,
+                    ServiceAction::Failed {
+                        error: e.to_string(),
+                    },
+                );
+                return Err(e);
+            }
+        };
+
+        // Finish progress bar and show result
+        pb.finish_and_clear();
+
+        // `run --detach` succeeds even for a container that crashes on boot
+        if let Some(window) = options.confirm_running
+            && let Err(e) = self.confirm_running(&label, container, window, ui).await
+        {
+            self.containers.insert(container.to_string(), failed);
+            ui.service_event(
+                &label,
+                ServiceAction::Failed {
+                    error: e.to_string(),
+                },
+            );
+            return Err(e);
+        }
+
+        let started = Container {
+            service: service_name.to_string(),
+            status: ContainerStatus::Running,
+        };
+
+        self.containers.insert(container.to_string(), started);
+        ui.service_event(&label, ServiceAction::Started { container_id });
+
+        Ok(())
+    }
+
+    // Point out a container that stopped while it should be running, with its
+    // exit code and last log lines, giving back the error to fail with
+    async fn report_exit(&self, label: &str, container: &str, ui: &dyn Reporter) -> ComposeError {
+        let exit_code = self.container_exit_code(container).await.unwrap_or(1);
+        ui.inline_warning(&format!(
+            "{} exited with code {}, last {} log line(s):",
+            label, exit_code, FAILED_START_LOG_LINES
+        ));
+        for line in self.tail_logs(container, FAILED_START_LOG_LINES, ui).await {
+            ui.log_line(label, label.len(), &line, true);
+        }
+        ComposeError::ServiceExited {
+            service: label.to_string(),
+            exit_code,
+        }
+    }
+
+    // Watch a freshly started container for `window` seconds, failing with its
+    // exit code and last log lines if it stops before then
+    async fn confirm_running(
+        &self,
+        label: &str,
+        container: &str,
+        window: u64,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(window);
+
+        loop {
+            let running = self.get_container_names(false).await?;
+            if !running.iter().any(|c| c == container) {
+                return Err(self.report_exit(label, container, ui).await);
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(());
+            }
+            tokio::time::sleep(LIVENESS_POLL_INTERVAL).await;
+        }
+    }
+
+    // Read the last lines a container logged, stdout and stderr together
+    async fn tail_logs(&self, container: &str, lines: usize, ui: &dyn Reporter) -> Vec<String> {
+        let mut cmd = self.container_command();
+        cmd.args(["logs", "-n", &lines.to_string(), container]);
+
+        match run_logged(&mut cmd, ui).await {
+            Ok(output) => [output.stdout, output.stderr]
+                .iter()
+                .flat_map(|bytes| {
+                    String::from_utf8_lossy(bytes)
+                        .lines()
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Get the order to start services based on dependencies
+    fn get_start_order(&self) -> Result<Vec<String>> {
+        Ok(self.service_names(&self.start_order()?))
+    }
+
+    // The start order as indices into the config's services, so sorting never
+    // copies or hashes service names
+    fn start_order(&self) -> Result<Vec<usize>> {
+        let count = self.config.services.len();
+        let mut order = Vec::with_capacity(count);
+        let mut marks = vec![VisitState::New; count];
+
+        // Visit services in declaration order so the order is the same on every run
+        for index in 0..count {
+            self.visit_service(index, &mut order, &mut marks)?;
+        }
+
+        Ok(order)
+    }
+
+    // The names of the services at `indices` in the config
+    fn service_names(&self, indices: &[usize]) -> Vec<String> {
+        indices
+            .iter()
+            .filter_map(|&index| self.config.services.get_index(index))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    // Group services into levels that can start together: each service sits one
+    // level above the deepest of its dependencies
+    fn get_start_levels(&self) -> Result<Vec<Vec<String>>> {
+        let services = &self.config.services;
+        let mut depths = vec![0; services.len()];
+        let mut levels: Vec<Vec<String>> = Vec::new();
+
+        for index in self.start_order()? {
+            let Some((service_name, service)) = services.get_index(index) else {
+                continue;
+            };
+            let depth = service
+                .depends_on
+                .iter()
+                .filter_map(|dep| services.get_index_of(dep))
+                .map(|dep| depths[dep] + 1)
+                .max()
+                .unwrap_or(0);
+
+            if levels.len() <= depth {
+                levels.resize(depth + 1, Vec::new());
+            }
+            levels[depth].push(service_name.clone());
+            depths[index] = depth;
+        }
+
+        Ok(levels)
+    }
+
+    // Get every service that depends on `service_name`, directly or transitively
+    fn get_dependents(&self, service_name: &str) -> std::collections::HashSet<String> {
+        let mut dependents = std::collections::HashSet::new();
+        let mut pending = vec![service_name.to_string()];
+
+        while let Some(current) = pending.pop() {
+            for (name, service) in &self.config.services {
+                if service.depends_on.contains(&current) && dependents.insert(name.clone()) {
+                    pending.push(name.clone());
+                }
+            }
+        }
+
+        dependents
+    }
+
+    // Recursive function for topological sort (dependency resolution): append
+    // the service at `index` to `order` after everything it depends on
+    fn visit_service(
+        &self,
+        index: usize,
+        order: &mut Vec<usize>,
+        marks: &mut [VisitState],
+    ) -> Result<()> {
+        let Some((service_name, service)) = self.config.services.get_index(index) else {
+            return Ok(());
+        };
+
+        match marks[index] {
+            VisitState::Visiting => {
+                return Err(ComposeError::CircularDependency(service_name.clone()));
+            }
+            VisitState::Visited => return Ok(()),
+            VisitState::New => {}
+        }
+
+        marks[index] = VisitState::Visiting;
+
+        // Dependencies missing from the config are reported by validation
+        for dep in &service.depends_on {
+            if let Some(dep_index) = self.config.services.get_index_of(dep) {
+                self.visit_service(dep_index, order, marks)?;
+            }
+        }
+
+        marks[index] = VisitState::Visited;
+        order.push(index);
+
+        Ok(())
+    }
+
+    // Restart one service (optionally cascading to its dependents) or every service
+    pub async fn restart(
+        &self,
+        service_name: Option<String>,
+        cascade: bool,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let start_order = self.get_start_order()?;
+
+        let targets: Vec<String> = match service_name {
+            Some(name) => {
+                if !self.config.services.contains_key(&name) {
+                    return Err(ComposeError::ServiceNotFound(name.to_string()));
+                }
+
+                let mut selected = if cascade {
+                    self.get_dependents(&name)
+                } else {
+                    std::collections::HashSet::new()
+                };
+                selected.insert(name);
+
+                // Dependents come back after the service they depend on
+                start_order
+                    .into_iter()
+                    .filter(|s| selected.contains(s))
+                    .collect()
+            }
+            None => start_order,
+        };
+
+        let instances = self.get_service_instances().await?;
+        for service_name in &targets {
+            for container in self.containers_of(service_name, &instances) {
+                let label = self.instance_label(service_name, &container);
+                self.restart_service_with_progress(&label, &container, ui)
+                    .await?;
+            }
+        }
+
+        ui.success(&format!("Restarted {} service(s)", targets.len()));
+        Ok(())
+    }
+
+    // Pull the image of each service (or just the named one) and recreate the
+    // containers whose image digest changed, in dependency order. With
+    // `cascade`, the dependents of a recreated service are restarted as well.
+    pub async fn update(
+        &mut self,
+        service_name: Option<String>,
+        cascade: bool,
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<()> {
+        let start_order = self.get_start_order()?;
+        let targets: Vec<String> = match service_name {
+            Some(name) => {
+                if !self.config.services.contains_key(&name) {
+                    return Err(ComposeError::ServiceNotFound(name));
+                }
+                vec![name]
+            }
+            None => start_order.clone(),
+        };
+
+        // Pull each image once, noting which ones now resolve to a new digest
+        let mut images: HashMap<String, bool> = HashMap::new();
+        for name in &targets {
+            let image = self.config.services[name].image.clone();
+            if images.contains_key(&image) {
+                continue;
+            }
+            if self.builds_locally(name) {
+                images.insert(image, false);
+                continue;
+            }
+            let before = Self::resolve_image_digest(&self.binary, &image).await;
+            self.pull_image(&image, ui).await?;
+            let after = Self::resolve_image_digest(&self.binary, &image).await;
+            images.insert(image, before != after);
+        }
+
+        let changed: Vec<&String> = targets
+            .iter()
+            .filter(|name| images[&self.config.services[*name].image])
+            .collect();
+        let mut dependents = std::collections::HashSet::new();
+        if cascade {
+            for name in &changed {
+                dependents.extend(self.get_dependents(name));
+            }
+        }
+
+        // Recreated containers mount the same volumes and networks as under `up`
+        if !changed.is_empty() {
+            self.initialize_volumes(ui).await?;
+            self.initialize_networks(ui).await?;
+        }
+
+        let instances = self.get_service_instances().await?;
+        let host_env: HashMap<String, String> = std::env::vars().collect();
+        let mut recreated = 0;
+        let mut restarted = 0;
+        for service_name in &start_order {
+            // Services that were never brought up only get their image pulled
+            let containers: Vec<String> = instances
+                .iter()
+                .filter(|(service, _)| service == service_name)
+                .map(|(_, container)| container.clone())
+                .collect();
+            if containers.is_empty() {
+                continue;
+            }
+            if changed.contains(&service_name) {
+                let service =
+                    self.config.services[service_name].interpolated(service_name, &host_env)?;
+                for container in &containers {
+                    let label = self.instance_label(service_name, container);
+                    ui.inline_info(&format!("{} image changed, recreating", label));
+                    self.stop_container_with_progress(
+                        &label,
+                        container,
+                        DEFAULT_STOP_TIMEOUT,
+                        true,
+                        ui,
+                        verbose,
+                    )
+                    .await?;
+                    self.containers.remove(container);
+                    self.start_service_with_progress(
+                        service_name,
+                        &service,
+                        container,
+                        &UpOptions::default(),
+                        ui,
+                        verbose,
+                    )
+                    .await?;
+                }
+                recreated += 1;
+            } else if dependents.contains(service_name) {
+                for container in &containers {
+                    let label = self.instance_label(service_name, container);
+                    self.restart_service_with_progress(&label, container, ui)
+                        .await?;
+                }
+                restarted += 1;
+            }
+        }
+
+        ui.success(&format!(
+            "Recreated {} service(s), restarted {} dependent(s), {} unchanged",
+            recreated,
+            restarted,
+            targets.len() - changed.len()
+        ));
+        Ok(())
+    }
+
+    // Restart a single service container with a progress bar labeled `label`
+    async fn restart_service_with_progress(
+        &self,
+        label: &str,
+        container: &str,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        ui.service_event(label, ServiceAction::Restarting);
+        let pb = ui.create_restart_progress(label);
+
+        let stop_output = timeout(
+            Duration::from_secs(DEFAULT_STOP_TIMEOUT),
+            run_logged(self.container_command().args(["stop", container]), ui),
+        )
+        .await;
+
+        if !matches!(&stop_output, Ok(Ok(output)) if output.status.success()) {
+            // Not responding to a graceful stop - force kill
+            run_logged(self.container_command().args(["kill", container]), ui).await?;
+        }
+
+        let output = run_logged(self.container_command().args(["start", container]), ui).await?;
+
+        pb.finish_and_clear();
+
+        if output.status.success() {
+            ui.service_event(label, ServiceAction::Restarted);
+            Ok(())
+        } else {
+            Err(ComposeError::ContainerCommandFailed {
+                action: "restart service".to_string(),
+                target: label.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            })
+        }
+    }
+
+    // Stream logs from one service, or every service container interleaved behind a
+    // service prefix; both go through the same tail, filter and prefix handling,
+    // and a scaled service shows every replica
+    pub async fn logs(
+        &self,
+        service: Option<&str>,
+        options: &LogOptions,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let instances = self.get_service_instances().await?;
+        let containers: Vec<(String, String)> = match service {
+            Some(name) => {
+                if !self.config.services.contains_key(name) {
+                    return Err(ComposeError::ServiceNotFound(name.to_string()));
+                }
+                self.containers_of(name, &instances)
+                    .into_iter()
+                    .map(|container| (self.instance_label(name, &container), container))
+                    .collect()
+            }
+            None => instances
+                .iter()
+                .map(|(service, container)| {
+                    (self.instance_label(service, container), container.clone())
+                })
+                .collect(),
+        };
+
+        if containers.is_empty() {
+            ui.info("No containers to show logs for");
+            return Ok(());
+        }
+
+        // Align every prefix into a gutter as wide as the longest service name
+        let width = containers.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<(String, String)>();
+        let mut children = Vec::new();
+
+        for (label, container) in &containers {
+            let mut cmd = self.container_command();
+            cmd.arg("logs");
+
+            if options.follow {
+                cmd.arg("-f");
+            }
+            if let Some(tail) = options.tail {
+                cmd.args(["-n", &tail.to_string()]);
+            }
+
+            cmd.arg(container);
+            cmd.stdout(Stdio::piped());
+            cmd.stderr(Stdio::piped());
+            cmd.kill_on_drop(true);
+
+            ui.command(&cmd);
+            let mut child = cmd.spawn()?;
+            if let Some(stdout) = child.stdout.take() {
+                forward_lines(label, stdout, tx.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                forward_lines(label, stderr, tx.clone());
+            }
+            children.push(child);
+        }
+
+        // Drop our sender so the loop ends once every stream is closed
+        drop(tx);
+
+        while let Some((service_name, line)) = rx.recv().await {
+            if let Some(pattern) = &options.grep
+                && !line.contains(pattern.as_str())
+            {
+                continue;
+            }
+            if options.no_log_prefix {
+                ui.log_raw(&line);
+            } else {
+                ui.log_line(&service_name, width, &line, !options.no_color);
+            }
+        }
+
+        for ((label, _), mut child) in containers.iter().zip(children) {
+            if !child.wait().await?.success() {
+                return Err(ComposeError::ContainerCommandFailed {
+                    action: "get logs for service".to_string(),
+                    target: label.clone(),
+                    stderr: String::new(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Pull images for services in dependency order: every service, one service, or
+    // one service and everything it depends on (`with_deps`). An image shared by
+    // several services is only pulled once.
+    pub async fn pull(
+        &self,
+        service_name: Option<String>,
+        with_deps: bool,
+        keep_going: bool,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let services_to_pull = match service_name {
+            Some(name) => {
+                if !self.config.services.contains_key(&name) {
+                    return Err(ComposeError::ServiceNotFound(name.to_string()));
+                }
+                if with_deps {
+                    // The service comes last, after the dependencies it needs
+                    let mut order = Vec::new();
+                    let mut marks = vec![VisitState::New; self.config.services.len()];
+                    if let Some(index) = self.config.services.get_index_of(&name) {
+                        self.visit_service(index, &mut order, &mut marks)?;
+                    }
+                    self.service_names(&order)
+                } else {
+                    vec![name]
+                }
+            }
+            None => self.get_start_order()?,
+        };
+
+        // With `keep_going`, a failed pull doesn't stop the remaining ones. Each
+        // image's local digest before and after tells whether anything changed.
+        let mut pulled: Vec<&str> = Vec::new();
+        let mut outcomes: Vec<(&str, &str, String)> = Vec::new();
+        let mut failures: Vec<(String, ComposeError)> = Vec::new();
+        for name in &services_to_pull {
+            let image = self.config.services[name].image.as_str();
+            if pulled.contains(&image) {
+                continue;
+            }
+            if self.builds_locally(name) {
+                ui.info(&format!(
+                    "Skipping service '{}', its image is built locally",
+                    name
+                ));
+                continue;
+            }
+            ui.info(&format!("Pulling image for service '{}'", name));
+            let before = Self::resolve_image_digest(&self.binary, image).await;
+            match self.pull_image(image, ui).await {
+                Ok(()) => {
+                    let after = Self::resolve_image_digest(&self.binary, image).await;
+                    let status = if after.is_some() && after == before {
+                        "up to date"
+                    } else {
+                        "pulled"
+                    };
+                    outcomes.push((image, status, after.unwrap_or_else(|| "-".to_string())));
+                }
+                Err(e) if keep_going => {
+                    outcomes.push((image, "failed", "-".to_string()));
+                    failures.push((name.clone(), e));
+                }
+                Err(e) => return Err(e),
+            }
+            pulled.push(image);
+        }
+
+        ui.separator();
+        ui.table_header(&["IMAGE", "STATUS", "DIGEST"]);
+        for (image, status, digest) in &outcomes {
+            let color = match *status {
+                "up to date" => "green",
+                "pulled" => "yellow",
+                _ => "red",
+            };
+            ui.table_row(&[image, status, digest], Some(color));
+        }
+
+        if !failures.is_empty() {
+            ui.warning(&format!(
+                "Pulled {} image(s), {} failed",
+                pulled.len() - failures.len(),
+                failures.len()
+            ));
+            return Err(ComposeError::ServicesFailed {
+                action: "pull images for".to_string(),
+                failures,
+            });
+        }
+
+        ui.success("All images pulled successfully");
+        Ok(())
+    }
+
+    // Build the images of services with a `build` section, in dependency order
+    pub async fn build(
+        &self,
+        service_name: Option<String>,
+        options: &BuildOptions,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let services_to_build = match service_name {
+            Some(name) => {
+                let service = self
+                    .config
+                    .services
+                    .get(&name)
+                    .ok_or_else(|| ComposeError::ServiceNotFound(name.clone()))?;
+                if service.build.is_none() {
+                    ui.warning(&format!(
+                        "Service '{}' has no build section, nothing to build",
+                        name
+                    ));
+                    return Ok(());
+                }
+                vec![name]
+            }
+            None => self
+                .get_start_order()?
+                .into_iter()
+                .filter(|name| self.config.services[name].build.is_some())
+                .collect(),
+        };
+
+        let host_env: HashMap<String, String> = std::env::vars().collect();
+        for name in &services_to_build {
+            let service = self.config.services[name].interpolated(name, &host_env)?;
+            let Some(build) = &service.build else {
+                continue;
+            };
+            ui.info(&format!("Building image for service '{}'", name));
+            self.build_image(name, &service.image, build, options, ui)
+                .await?;
+        }
+
+        ui.success(&format!("Built {} image(s)", services_to_build.len()));
+        Ok(())
+    }
+
+    // Run `container build` for one service, tagging the result with its image
+    async fn build_image(
+        &self,
+        service_name: &str,
+        image: &str,
+        build: &Build,
+        options: &BuildOptions,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let context = resolve_path(&self.project_dir, &build.context);
+
+        let mut cmd = self.container_command();
+        cmd.args(["build", "--tag", image]);
+        // Like docker-compose, the Dockerfile path is relative to the context
+        if let Some(dockerfile) = &build.dockerfile {
+            cmd.arg("--file").arg(context.join(dockerfile));
+        }
+        if options.no_cache {
+            cmd.arg("--no-cache");
+        }
+        if options.pull {
+            cmd.arg("--pull");
+        }
+        for arg in &build.args {
+            cmd.args(["--build-arg", arg]);
+        }
+        cmd.arg(&context);
+
+        let output = run_logged(&mut cmd, ui).await?;
+        if !output.status.success() {
+            return Err(ComposeError::ContainerCommandFailed {
+                action: "build service".to_string(),
+                target: service_name.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
+
+        ui.success(&format!("Built {} ({})", service_name, image));
+        Ok(())
+    }
+
+    // Pull a specific image
+    async fn pull_image(&self, image: &str, ui: &dyn Reporter) -> Result<()> {
+        let mut cmd = self.container_command();
+        cmd.args(["images", "pull", image]);
+
+        // Create progress bar, made determinate once the pull reports a percentage
+        let pb = ui.create_pull_progress(image);
+
+        let output = run_streamed(&mut cmd, ui, |line| match parse_percent(line) {
+            Some(percent) => ui.set_pull_percent(&pb, percent),
+            None => ui.set_pull_status(&pb, image, line),
+        })
+        .await?;
+
+        pb.finish_and_clear();
+
+        if output.status.success() {
+            ui.inline_success(&format!("Pulled {}", image));
+        } else {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(ComposeError::ContainerCommandFailed {
+                action: "pull image".to_string(),
+                target: image.to_string(),
+                stderr: error_msg.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Look up the content digest of a locally available image
+    pub async fn resolve_image_digest(binary: &Path, image: &str) -> Option<String> {
+        let output = AsyncCommand::new(binary)
+            .args(["images", "inspect", image])
+            .output()
+            .await
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+        find_json_key(&info, "digest")?
+            .as_str()
+            .filter(|digest| digest.starts_with("sha256:"))
+            .map(|digest| digest.to_string())
+    }
+
+    // Run a container with progress (used by start_service_with_progress); `service`
+    // has `${VAR}` resolved already, so it sees the environment at start time
+    async fn run_container_with_progress(
+        &self,
+        name: &str,
+        container: &str,
+        service: &Service,
+        ui: &dyn Reporter,
+    ) -> Result<String> {
+        let mut cmd = self.container_command();
+        cmd.args(["run", "--detach", "--name", container]);
+
+        // Label the container so later runs can tell which project and service it belongs to
+        cmd.args([
+            "--label",
+            &format!("{}={}", PROJECT_LABEL, self.project_name),
+        ]);
+        cmd.args(["--label", &format!("{}={}", SERVICE_LABEL, name)]);
+        cmd.args(["--label", &format!("{}={}", CREATED_LABEL, unix_time())]);
+
+        // Hash what the container actually runs, so a changed variable recreates it
+        cmd.args([
+            "--label",
+            &format!("{}={}", CONFIG_HASH_LABEL, service.config_hash()),
+        ]);
+
+        self.add_service_options(&mut cmd, service, Some(name))?;
+
+        // Allocate a terminal and keep stdin open for services that expect one
+        if service.tty == Some(true) {
+            cmd.arg("--tty");
+        }
+        if service.stdin_open == Some(true) {
+            cmd.arg("--interactive");
+        }
+
+        add_image_and_command(&mut cmd, service);
+
+        // Kill the run if the caller gives up waiting on it
+        cmd.kill_on_drop(true);
+        let output = run_logged(&mut cmd, ui).await?;
+
+        if output.status.success() {
+            let container_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            Ok(container_id)
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if is_name_conflict(&stderr) {
+                return Err(ComposeError::NameConflict {
+                    service: name.to_string(),
+                    container: container.to_string(),
+                });
             }
+            Err(ComposeError::ContainerCommandFailed {
+                action: "start container".to_string(),
+                target: name.to_string(),
+                stderr: stderr.to_string(),
+            })
+        }
+    }
 
-            let _rm_output = AsyncCommand::new("container")
-                .args(&["rm", service_name])
-                .output()
-                .await;
+    // The `container run` options a service's definition implies, shared by its
+    // containers and one-off runs: mounts, configs, environment, networks,
+    // working directory and init. A service's own containers pass its name as
+    // `service_alias`, so other services reach them as `<service>` whatever the
+    // container is called.
+    fn add_service_options(
+        &self,
+        cmd: &mut AsyncCommand,
+        service: &Service,
+        service_alias: Option<&str>,
+    ) -> Result<()> {
+        // Add volume mounts (handle both bind mounts and named volumes)
+        for volume in &service.volumes {
+            // Warnings were already reported by `initialize_volumes`
+            let volume_spec = self.process_volume_mount(volume, &mut Vec::new())?;
+            cmd.args(["--volume", &volume_spec]);
+        }
+
+        // Mount configs read-only at their target path
+        for service_config in &service.configs {
+            let config = self
+                .config
+                .configs
+                .get(&service_config.source)
+                .ok_or_else(|| ComposeError::ConfigNotFound(service_config.source.clone()))?;
+            let source = resolve_path(&self.project_dir, &config.file);
+            cmd.args([
+                "--volume",
+                &format!(
+                    "{}:{}:ro",
+                    source.to_string_lossy(),
+                    service_config.target_path()
+                ),
+            ]);
+        }
+
+        // Add environment variables
+        for env in &service.environment {
+            cmd.args(["--env", env]);
+        }
+
+        // Attach to networks, with any aliases and static address
+        let default_network = ("default".to_string(), ServiceNetwork::default());
+        let mut networks: Vec<_> = service.networks.iter().collect();
+        networks.sort_by(|a, b| a.0.cmp(b.0));
+        // The alias needs a network to live on, so name the default one explicitly
+        if networks.is_empty() && service_alias.is_some() {
+            networks.push((&default_network.0, &default_network.1));
+        }
+        for (network, options) in networks {
+            cmd.args(["--network", network]);
+            if let Some(alias) = service_alias
+                && !options.aliases.iter().any(|a| a == alias)
+            {
+                cmd.args(["--network-alias", alias]);
+            }
+            for alias in &options.aliases {
+                cmd.args(["--network-alias", alias]);
+            }
+            if let Some(address) = &options.ipv4_address {
+                cmd.args(["--ip", address]);
+            }
+        }
+
+        // Set working directory if specified
+        if let Some(working_dir) = &service.working_dir {
+            cmd.args(["--workdir", working_dir]);
+        }
+
+        // Run an init process as PID 1 to reap zombies
+        if service.init == Some(true) {
+            cmd.arg("--init");
+        }
+
+        Ok(())
+    }
+
+    // Get the names of our containers, only the running ones unless `all`. Only
+    // containers labeled for this project count, so a container of another
+    // project or one made by hand that happens to share a name is never ours.
+    async fn get_container_names(&self, all: bool) -> Result<Vec<String>> {
+        let index = self.container_index();
+        let mut names: Vec<String> = self
+            .get_project_containers()
+            .await?
+            .into_iter()
+            .filter(|c| all || c.running)
+            .filter(|c| index.service(&c.id).is_some())
+            .map(|c| c.id)
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    // Get the services with at least one running container
+    async fn get_running_containers(&self) -> Result<Vec<String>> {
+        let names = self.get_container_names(false).await?;
+        Ok(self.services_of(&names))
+    }
+
+    // Get the services with at least one container, running or stopped
+    async fn get_all_service_containers(&self) -> Result<Vec<String>> {
+        let names = self.get_container_names(true).await?;
+        Ok(self.services_of(&names))
+    }
+
+    // The services the given containers belong to, in declaration order
+    fn services_of(&self, containers: &[String]) -> Vec<String> {
+        let index = self.container_index();
+        let owners: Vec<&String> = containers.iter().filter_map(|c| index.service(c)).collect();
+        self.config
+            .services
+            .keys()
+            .filter(|service_name| owners.contains(service_name))
+            .cloned()
+            .collect()
+    }
+
+    // Get every container of ours (running and stopped) as (service, container)
+    // pairs, grouped by service in declaration order
+    async fn get_service_instances(&self) -> Result<Vec<(String, String)>> {
+        let index = self.container_index();
+        let mut instances: Vec<(String, String)> = self
+            .get_container_names(true)
+            .await?
+            .into_iter()
+            .filter_map(|container| Some((index.service(&container)?.clone(), container)))
+            .collect();
+        instances.sort_by_key(|(service, _)| self.config.services.get_index_of(service));
+        Ok(instances)
+    }
+
+    // Get every container labeled as part of this project, running or stopped
+    async fn get_project_containers(&self) -> Result<Vec<ProjectContainer>> {
+        let output = self
+            .container_command()
+            .args(["list", "--all", "--format", "json"])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+
+        let list: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+        let mut containers: Vec<ProjectContainer> = list
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let labels = find_json_key(item, "labels")?;
+                if labels.get(PROJECT_LABEL)?.as_str()? != self.project_name
+                    || labels.get(ONEOFF_LABEL).is_some()
+                {
+                    return None;
+                }
+                Some(ProjectContainer {
+                    id: find_json_key(item, "id")?.as_str()?.to_string(),
+                    service: labels.get(SERVICE_LABEL)?.as_str()?.to_string(),
+                    config_hash: labels
+                        .get(CONFIG_HASH_LABEL)
+                        .and_then(|hash| hash.as_str())
+                        .map(str::to_string),
+                    running: item.get("status").and_then(|status| status.as_str())
+                        == Some("running"),
+                    created: labels
+                        .get(CREATED_LABEL)
+                        .and_then(|created| created.as_str()?.parse().ok()),
+                })
+            })
+            .collect();
+        containers.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(containers)
+    }
+
+    // Get this project's containers whose service is no longer in the config;
+    // services only left out by an inactive profile still count as in it
+    async fn get_orphans(&self) -> Result<Vec<ProjectContainer>> {
+        let mut containers = self.get_project_containers().await?;
+        containers.retain(|c| {
+            !self.config.services.contains_key(&c.service)
+                && !self.config.disabled_services.contains(&c.service)
+        });
+        Ok(containers)
+    }
+
+    // Stop a container, and remove it too if `remove`, with a progress bar labeled
+    // `name` (a service, or the container itself for orphans). A zero timeout
+    // skips the graceful stop and kills right away.
+    async fn stop_container_with_progress(
+        &self,
+        name: &str,
+        container: &str,
+        stop_timeout: u64,
+        remove: bool,
+        ui: &dyn Reporter,
+        verbose: bool,
+    ) -> Result<()> {
+        // Create progress bar for stopping
+        ui.service_event(name, ServiceAction::Stopping);
+        let pb = ui.create_stop_progress(name);
+
+        let stop_result = if stop_timeout == 0 {
+            None
+        } else {
+            // Try to stop the container gracefully first with timeout
+            Some(
+                timeout(
+                    Duration::from_secs(stop_timeout),
+                    run_logged(self.container_command().args(["stop", container]), ui),
+                )
+                .await,
+            )
+        };
+
+        let mut output = match stop_result {
+            Some(Ok(result)) => result?,
+            None => {
+                // Fast-kill path - no graceful stop attempted
+                run_logged(self.container_command().args(["kill", container]), ui).await?
+            }
+            Some(Err(_)) => {
+                // Timeout - container is not responding, force kill
+                run_logged(self.container_command().args(["kill", container]), ui).await?
+            }
+        };
+
+        // If graceful stop failed, try force kill
+        if !output.status.success() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            if !error_msg.contains("no such container") && !error_msg.contains("not found") {
+                // Try force kill
+                output = run_logged(self.container_command().args(["kill", container]), ui).await?;
+
+                // If kill also failed, try one more time after a brief delay
+                if !output.status.success() && verbose {
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                    output =
+                        run_logged(self.container_command().args(["kill", container]), ui).await?;
+                }
+            }
+        }
+
+        // Finish progress bar and show result
+        pb.finish_and_clear();
+
+        if output.status.success() {
+            ui.service_event(name, ServiceAction::Stopped);
+
+            // Also try to remove the container
+            if remove {
+                let _rm_output =
+                    run_logged(self.container_command().args(["rm", container]), ui).await;
+            }
         } else {
             let error_msg = String::from_utf8_lossy(&output.stderr);
             if error_msg.contains("no such container") || error_msg.contains("not found") {
-                ui.inline_info(&format!("{} not found", service_name));
+                ui.service_event(name, ServiceAction::NotFound);
             } else {
-                ui.inline_warning(&format!(
-                    "{} failed to stop (tried stop and kill)",
-                    service_name
-                ));
+                ui.inline_warning(&format!("{} failed to stop (tried stop and kill)", name));
+                ui.service_event(
+                    name,
+                    ServiceAction::Failed {
+                        error: error_msg.trim().to_string(),
+                    },
+                );
+                return Err(ComposeError::ContainerCommandFailed {
+                    action: "stop container".to_string(),
+                    target: container.to_string(),
+                    stderr: error_msg.to_string(),
+                });
             }
         }
 
         Ok(())
     }
 
-    // List all services and their status
-    pub async fn ps(&self, ui: &UI) -> Result<()> {
+    // List all services and their status, as a table, through a `--format`
+    // template, or as bare service names (`--services`) or container IDs (`-q`).
+    // With `--orphans` the rows are this project's containers whose service is
+    // no longer in the config instead.
+    pub async fn ps(&self, options: &PsOptions, ui: &dyn Reporter) -> Result<()> {
+        // Reject unknown placeholders and filters before querying anything
+        if let Some(template) = &options.format {
+            render_template(template, &PsRow::default().fields())?;
+        }
+        let state_filter = ps_state_filter(&options.filter)?;
+        // Restart counts cost an inspect per container, so only look them up when shown
+        let with_restarts = options.restarts.is_some()
+            || options
+                .format
+                .as_ref()
+                .is_some_and(|template| template.contains(".Restarts"));
+
+        let service_names: Vec<&String> = if options.orphans {
+            Vec::new()
+        } else {
+            self.config.services.keys().collect()
+        };
+
+        // Listing every service needs nothing from the container framework
+        if options.services && state_filter.is_none() && !options.orphans {
+            for service_name in service_names {
+                ui.output(service_name);
+            }
+            return Ok(());
+        }
+
         // Get all containers (running and stopped) for our services
-        let all_containers = self.get_all_service_containers().await?;
-        let running_containers = self.get_running_containers().await?;
+        let instances = self.get_service_instances().await?;
+        let running_containers = self.get_container_names(false).await?;
+
+        // Probing health runs the healthchecks, so skip it when no status is
+        // shown, and run them side by side rather than one after another
+        let mut health: HashMap<String, Health> = HashMap::new();
+        if !options.services && !options.quiet && !options.orphans {
+            let created: HashMap<String, u64> = self
+                .get_project_containers()
+                .await?
+                .into_iter()
+                .filter_map(|c| Some((c.id, c.created?)))
+                .collect();
+            let probes: Vec<(&Service, &String)> = instances
+                .iter()
+                .filter(|(_, container)| running_containers.contains(container))
+                .map(|(service, container)| (&self.config.services[service], container))
+                .filter(|(service, _)| service.healthcheck.is_some())
+                .collect();
+            let created = &created;
+            health = stream::iter(probes)
+                .map(|(service, container)| async move {
+                    let age = created
+                        .get(container)
+                        .map(|created| Duration::from_secs(unix_time().saturating_sub(*created)));
+                    let health = self.container_health(service, container, age, ui).await;
+                    (container.clone(), health)
+                })
+                .buffer_unordered(PS_HEALTH_PROBES)
+                .filter_map(|(container, health)| async move { Some((container, health?)) })
+                .collect()
+                .await;
+        }
 
-        ui.table_header(&["SERVICE", "STATUS", "CONTAINER ID", "IMAGE"]);
+        let mut rows = Vec::new();
 
-        // Process each service defined in the config
-        for (service_name, service) in &self.config.services {
-            if all_containers.contains(service_name) {
+        // Process each service defined in the config, one row per container
+        for service_name in service_names {
+            let service = &self.config.services[service_name];
+            let ports = service.ports.join(", ");
+            let containers: Vec<&String> = instances
+                .iter()
+                .filter(|(service, _)| service == service_name)
+                .map(|(_, container)| container)
+                .collect();
+
+            for container in &containers {
                 // Container exists - determine if it's running or stopped
-                let is_running = running_containers.contains(service_name);
-                let status = if is_running { "Running" } else { "Stopped" };
-                let status_color = if is_running {
-                    Some("green")
-                } else {
-                    Some("red")
+                let is_running = running_containers.contains(container);
+                let (status, status_color) = match (is_running, health.get(*container)) {
+                    (false, _) => ("Stopped".to_string(), "red"),
+                    (true, None) => ("Running".to_string(), "green"),
+                    (true, Some(health)) => {
+                        (format!("Running ({})", health.label()), health.color())
+                    }
                 };
 
                 // Get container details
-                let container_details = self.get_container_details(service_name).await?;
-                ui.table_row(
-                    &[
-                        service_name,
-                        status,
-                        &container_details.0, // container ID
-                        &container_details.1, // image
-                    ],
+                let (container_id, image) =
+                    self.get_container_details(service_name, container).await?;
+                let restarts = if with_restarts {
+                    self.container_restart_count(container).await
+                } else {
+                    0
+                };
+                rows.push(PsRow {
+                    service: service_name.clone(),
+                    state: if is_running {
+                        ServiceState::Running
+                    } else {
+                        ServiceState::Created
+                    },
+                    status,
                     status_color,
-                );
-            } else {
+                    container_id,
+                    image,
+                    ports: ports.clone(),
+                    restarts: match options.restarts {
+                        Some(limit) if restarts > limit => format!("{} (>{})", restarts, limit),
+                        _ => restarts.to_string(),
+                    },
+                });
+            }
+
+            if containers.is_empty() {
                 // No container exists for this service
-                ui.table_row(
-                    &[service_name, "Not Created", "N/A", &service.image],
-                    Some("red"),
-                );
+                rows.push(PsRow {
+                    service: service_name.clone(),
+                    state: ServiceState::Absent,
+                    status: "Not Created".to_string(),
+                    status_color: "red",
+                    container_id: "N/A".to_string(),
+                    image: service.image.clone(),
+                    ports,
+                    restarts: "0".to_string(),
+                });
+            }
+        }
+
+        if options.orphans {
+            for orphan in self.get_orphans().await? {
+                let is_running = running_containers.contains(&orphan.id);
+                let (container_id, image) = self
+                    .get_container_details(&orphan.service, &orphan.id)
+                    .await?;
+                let restarts = if with_restarts {
+                    self.container_restart_count(&orphan.id).await
+                } else {
+                    0
+                };
+                rows.push(PsRow {
+                    service: orphan.service,
+                    state: if is_running {
+                        ServiceState::Running
+                    } else {
+                        ServiceState::Created
+                    },
+                    status: if is_running { "Running" } else { "Stopped" }.to_string(),
+                    status_color: if is_running { "green" } else { "red" },
+                    container_id,
+                    image,
+                    ports: String::new(),
+                    restarts: match options.restarts {
+                        Some(limit) if restarts > limit => format!("{} (>{})", restarts, limit),
+                        _ => restarts.to_string(),
+                    },
+                });
+            }
+        }
+
+        if let Some(state) = state_filter {
+            rows.retain(|row| row.state == state);
+        }
+
+        if options.services {
+            let mut services: Vec<&String> = rows.iter().map(|row| &row.service).collect();
+            services.dedup();
+            for service in services {
+                ui.output(service);
+            }
+            return Ok(());
+        }
+
+        if options.quiet {
+            for row in rows.iter().filter(|row| row.state != ServiceState::Absent) {
+                ui.output(&row.container_id);
+            }
+            return Ok(());
+        }
+
+        if let Some(template) = &options.format {
+            for row in &rows {
+                ui.output(&render_template(template, &row.fields())?);
+            }
+            return Ok(());
+        }
+
+        let mut headers = vec!["SERVICE", "STATUS", "CONTAINER ID", "IMAGE"];
+        if options.restarts.is_some() {
+            headers.push("RESTARTS");
+        }
+        ui.table_header(&headers);
+        for row in &rows {
+            let mut cells = vec![
+                row.service.as_str(),
+                &row.status,
+                &row.container_id,
+                &row.image,
+            ];
+            if options.restarts.is_some() {
+                cells.push(&row.restarts);
             }
+            ui.table_row(&cells, Some(row.status_color));
         }
 
         Ok(())
     }
 
+    // A running container's health for `ps`, None when its service has no
+    // healthcheck: what the container's inspect data reports, or else the
+    // result of running the healthcheck once, where a failure while the
+    // container is younger than the start period only means it's starting
+    async fn container_health(
+        &self,
+        service: &Service,
+        container: &str,
+        age: Option<Duration>,
+        ui: &dyn Reporter,
+    ) -> Option<Health> {
+        let check = service.healthcheck.as_ref()?;
+        let command = check.command()?;
+
+        if let Some(health) = self.inspected_health(container).await {
+            return Some(health);
+        }
+
+        let (passed, _) = self
+            .run_healthcheck(
+                container,
+                &command,
+                check.timeout().min(PS_HEALTH_PROBE_TIMEOUT),
+                ui,
+            )
+            .await;
+        Some(if passed {
+            Health::Healthy
+        } else if age.is_some_and(|age| age < check.start_period()) {
+            Health::Starting
+        } else {
+            Health::Unhealthy
+        })
+    }
+
+    // The health status in a container's inspect data, for runtimes that track it
+    async fn inspected_health(&self, container: &str) -> Option<Health> {
+        let output = self
+            .container_command()
+            .args(["inspect", container])
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+        let health = find_json_key(&info, "health").or_else(|| find_json_key(&info, "Health"))?;
+        let status = health
+            .as_str()
+            .or_else(|| find_json_key(health, "status")?.as_str())
+            .or_else(|| find_json_key(health, "Status")?.as_str())?;
+        match status.to_lowercase().as_str() {
+            "healthy" => Some(Health::Healthy),
+            "unhealthy" => Some(Health::Unhealthy),
+            "starting" => Some(Health::Starting),
+            _ => None,
+        }
+    }
+
     // Get container details (ID and image) from Apple's container list
-    async fn get_container_details(&self, service_name: &str) -> Result<(String, String)> {
-        let output = AsyncCommand::new("container")
-            .args(&["list", "--all"]) // Include stopped containers
+    async fn get_container_details(
+        &self,
+        service_name: &str,
+        container: &str,
+    ) -> Result<(String, String)> {
+        let output = self
+            .container_command()
+            .args(["list", "--all"]) // Include stopped containers
             .output()
             .await?;
 
@@ -566,7 +2741,7 @@ impl ContainerManager {
             for line in lines.iter().skip(1) {
                 if !line.is_empty() {
                     let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 2 && parts[0] == service_name {
+                    if parts.len() >= 2 && parts[0] == container {
                         // parts[0] = ID/Name, parts[1] = Image
                         let container_id = parts[0].to_string();
                         let image = parts[1].to_string();
@@ -574,64 +2749,205 @@ impl ContainerManager {
                     }
                 }
             }
-        }
+        }
+
+        // If we can't find it in containers, get image from config
+        if let Some(service) = self.config.services.get(service_name) {
+            Ok((service_name.to_string(), service.image.clone()))
+        } else {
+            Ok((service_name.to_string(), "unknown".to_string()))
+        }
+    }
+
+    // Print service lifecycle events as they happen, until Ctrl-C. Events are
+    // found by polling `container list` and diffing each service's state.
+    pub async fn events(
+        &self,
+        format: OutputFormat,
+        filters: &[String],
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let services = self.event_services(filters)?;
+
+        if format == OutputFormat::Text {
+            ui.info("Watching service events (press Ctrl-C to stop)");
+        }
+
+        tokio::select! {
+            result = self.watch_events(&services, format, ui) => result,
+            result = tokio::signal::ctrl_c() => Ok(result?),
+        }
+    }
+
+    // Resolve `--filter service=<name>` options into the services to watch
+    fn event_services(&self, filters: &[String]) -> Result<Vec<String>> {
+        let mut services = Vec::new();
+
+        for filter in filters {
+            let Some((key, value)) = filter.split_once('=') else {
+                return Err(ComposeError::InvalidFilter(format!(
+                    "Invalid filter '{}': expected KEY=VALUE",
+                    filter
+                )));
+            };
+            if key != "service" {
+                return Err(ComposeError::InvalidFilter(format!(
+                    "Unsupported filter key '{}'",
+                    key
+                )));
+            }
+            if !self.config.services.contains_key(value) {
+                return Err(ComposeError::ServiceNotFound(value.to_string()));
+            }
+            services.push(value.to_string());
+        }
+
+        // Watch in declaration order, every service when no filter names one
+        Ok(self
+            .config
+            .services
+            .keys()
+            .filter(|name| services.is_empty() || services.contains(name))
+            .cloned()
+            .collect())
+    }
+
+    // Poll the watched services forever, emitting an event for each state change
+    async fn watch_events(
+        &self,
+        services: &[String],
+        format: OutputFormat,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let mut states = self.service_states(services).await?;
+
+        loop {
+            tokio::time::sleep(EVENTS_POLL_INTERVAL).await;
+            let current = self.service_states(services).await?;
+
+            for service_name in services {
+                let action = match (states[service_name], current[service_name]) {
+                    (ServiceState::Absent, ServiceState::Created) => "create",
+                    (ServiceState::Absent | ServiceState::Created, ServiceState::Running) => {
+                        "start"
+                    }
+                    (ServiceState::Running, ServiceState::Created) => "die",
+                    (ServiceState::Running, ServiceState::Absent) => "stop",
+                    (ServiceState::Created, ServiceState::Absent) => "destroy",
+                    _ => continue,
+                };
+
+                let exit_code = if action == "die" {
+                    self.container_exit_code(&self.container_name(service_name))
+                        .await
+                } else {
+                    None
+                };
+
+                let event = ServiceEvent {
+                    time: unix_time(),
+                    service: service_name.clone(),
+                    action,
+                    exit_code,
+                };
+
+                match format {
+                    OutputFormat::Json => ui.output(&serde_json::to_string(&event)?),
+                    OutputFormat::Text => match event.exit_code {
+                        Some(code) => ui.info(&format!(
+                            "{} {} (exit code {})",
+                            event.service, event.action, code
+                        )),
+                        None => ui.info(&format!("{} {}", event.service, event.action)),
+                    },
+                }
+            }
 
-        // If we can't find it in containers, get image from config
-        if let Some(service) = self.config.services.get(service_name) {
-            Ok((service_name.to_string(), service.image.clone()))
-        } else {
-            Ok((service_name.to_string(), "unknown".to_string()))
+            states = current;
         }
     }
 
-    // Process volume mount - handle named volumes and bind mounts
-    fn process_volume_mount(&self, volume: &str) -> Result<String> {
+    // Snapshot whether each service's container is running, stopped or absent
+    async fn service_states(&self, services: &[String]) -> Result<HashMap<String, ServiceState>> {
+        let existing = self.get_all_service_containers().await?;
+        let running = self.get_running_containers().await?;
+
+        Ok(services
+            .iter()
+            .map(|service_name| {
+                let state = if running.contains(service_name) {
+                    ServiceState::Running
+                } else if existing.contains(service_name) {
+                    ServiceState::Created
+                } else {
+                    ServiceState::Absent
+                };
+                (service_name.clone(), state)
+            })
+            .collect())
+    }
+
+    // Process volume mount - turn a named volume or bind mount into the
+    // `--volume` argument, collecting non-fatal problems in `warnings`. Named
+    // volume directories are not created here; see `initialize_volumes`.
+    fn process_volume_mount(&self, volume: &str, warnings: &mut Vec<String>) -> Result<String> {
         if volume.contains(':') {
             let parts: Vec<&str> = volume.split(':').collect();
             if parts.len() >= 2 {
                 let host_path = parts[0];
                 let container_path = parts[1];
                 let rest = if parts.len() > 2 {
-                    format!(":{}", parts[2])
+                    let (kept, dropped) = parse_volume_mode(parts[2]).map_err(|reason| {
+                        ComposeError::InvalidVolume {
+                            volume: volume.to_string(),
+                            reason,
+                        }
+                    })?;
+
+                    // Docker-only options like SELinux relabeling would fail at runtime
+                    if !dropped.is_empty() {
+                        warnings.push(format!(
+                            "Ignoring unsupported volume mode '{}' on {}",
+                            dropped.join(","),
+                            volume
+                        ));
+                    }
+
+                    if kept.is_empty() {
+                        String::new()
+                    } else {
+                        format!(":{}", kept.join(","))
+                    }
                 } else {
                     String::new()
                 };
 
                 let abs_host_path = if self.is_named_volume(host_path) {
-                    // Named volume - create managed directory
-                    self.get_named_volume_path(host_path)?
+                    // Named volume - use its managed directory
+                    self.named_volume_dir(host_path)?
+                        .to_string_lossy()
+                        .to_string()
                 } else {
-                    // Bind mount - convert relative path to absolute
-                    let resolved_path = if host_path.starts_with("./")
-                        || (!host_path.starts_with('/') && !host_path.contains('/'))
-                    {
-                        let current_dir = std::env::current_dir()?;
-                        let path = if host_path.starts_with("./") {
-                            current_dir.join(&host_path[2..])
-                        } else {
-                            current_dir.join(host_path)
-                        };
-                        path.to_string_lossy().to_string()
-                    } else {
-                        host_path.to_string()
-                    };
+                    // Bind mount - resolve relative paths against the project directory
+                    let resolved_path = resolve_path(&self.project_dir, host_path)
+                        .to_string_lossy()
+                        .to_string();
 
                     // Validate that the source path exists
-                    let path = std::path::Path::new(&resolved_path);
+                    let path = Path::new(&resolved_path);
                     if !path.exists() {
-                        return Err(anyhow::anyhow!(
-                            "Volume mount source path does not exist: {} (resolved to: {})",
-                            host_path,
-                            resolved_path
-                        ));
+                        return Err(ComposeError::VolumeSourceMissing {
+                            source: host_path.to_string(),
+                            resolved: resolved_path,
+                        });
                     }
 
                     // Warn if path contains spaces (potential issue with Apple's container framework)
                     if resolved_path.contains(' ') {
-                        eprintln!(
-                            "Warning: Volume path contains spaces, this may cause issues: {}",
+                        warnings.push(format!(
+                            "Volume path contains spaces, this may cause issues: {}",
                             resolved_path
-                        );
+                        ));
                     }
 
                     resolved_path
@@ -643,10 +2959,7 @@ impl ContainerManager {
             }
         } else {
             // Anonymous volume - not supported by Apple's container framework
-            Err(anyhow::anyhow!(
-                "Anonymous volumes are not supported: {}",
-                volume
-            ))
+            Err(ComposeError::AnonymousVolume(volume.to_string()))
         }
     }
 
@@ -655,57 +2968,415 @@ impl ContainerManager {
         self.config.volumes.contains_key(volume_name)
     }
 
-    // Get the host path for a named volume
-    fn get_named_volume_path(&self, volume_name: &str) -> Result<String> {
-        // Use a global volumes directory in user's home directory for consistency
-        let home_dir = std::env::var("HOME")
+    // Get the directory that holds all named volumes. In order of preference:
+    // `--volumes-dir`, `$CONTAINER_COMPOSE_HOME/volumes`, the legacy
+    // `~/.container-compose/volumes` if it already exists,
+    // `$XDG_DATA_HOME/container-compose/volumes`, then the legacy default.
+    pub fn volumes_base_dir(override_dir: Option<&Path>) -> Result<PathBuf> {
+        if let Some(dir) = override_dir {
+            return Ok(dir.to_path_buf());
+        }
+
+        if let Some(home) = std::env::var_os("CONTAINER_COMPOSE_HOME").filter(|v| !v.is_empty()) {
+            return Ok(PathBuf::from(home).join("volumes"));
+        }
+
+        let legacy_dir = std::env::var("HOME")
             .or_else(|_| std::env::var("USERPROFILE"))
-            .map_err(|_| anyhow::anyhow!("Could not find home directory"))?;
+            .map(|home| Path::new(&home).join(".container-compose").join("volumes"))
+            .map_err(|_| ComposeError::NoHomeDirectory);
+
+        // Keep using existing data rather than silently moving to a new location
+        if let Ok(dir) = &legacy_dir
+            && dir.is_dir()
+        {
+            return Ok(dir.clone());
+        }
+
+        if let Some(data_home) = std::env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty()) {
+            return Ok(PathBuf::from(data_home)
+                .join("container-compose")
+                .join("volumes"));
+        }
+
+        legacy_dir
+    }
+
+    // Get the managed directory for a named volume without creating it
+    fn named_volume_dir(&self, volume_name: &str) -> Result<PathBuf> {
+        Ok(Self::volumes_base_dir(self.volumes_dir.as_deref())?.join(volume_name))
+    }
+
+    // Resolve every volume mount of every service up front, failing with all
+    // the problems at once so a bad mount can't leave half the stack running,
+    // then create the directories of the named volumes services mount, each
+    // once and one after the other, as a create_dir_all is too cheap to be
+    // worth spawning for. Unused declarations are skipped, so the volumes
+    // directory is only looked up when needed and stacks without named
+    // volumes work where no home is set.
+    pub async fn initialize_volumes(&self, ui: &dyn Reporter) -> Result<()> {
+        let mut problems = Vec::new();
+        let mut warnings = Vec::new();
+        for (name, service) in &self.config.services {
+            for volume in &service.volumes {
+                if let Err(e) = self.process_volume_mount(volume, &mut warnings) {
+                    problems.push((name.clone(), e));
+                }
+            }
+        }
+
+        for (i, warning) in warnings.iter().enumerate() {
+            if !warnings[..i].contains(warning) {
+                ui.warning(warning);
+            }
+        }
+        if !problems.is_empty() {
+            return Err(ComposeError::InvalidMounts(problems));
+        }
+
+        for name in self.config.mounted_volumes() {
+            std::fs::create_dir_all(self.named_volume_dir(name)?)?;
+        }
+
+        Ok(())
+    }
+
+    // Delete the data directory of every declared named volume (down --volumes)
+    pub fn remove_volumes(&self, ui: &dyn Reporter) -> Result<()> {
+        for name in self.config.volumes.keys() {
+            let path = self.named_volume_dir(name)?;
+            if path.is_dir() {
+                std::fs::remove_dir_all(&path)?;
+                ui.inline_success(&format!("Volume {} removed", name));
+            }
+        }
+
+        Ok(())
+    }
+
+    // The named volumes whose data `remove_volumes` would delete, with their
+    // data directories; empty ones lose nothing, so they aren't listed
+    pub fn volumes_with_data(&self) -> Result<Vec<(String, PathBuf)>> {
+        let mut volumes = Vec::new();
+        for name in self.config.volumes.keys() {
+            if self.volume_has_data(name)? {
+                volumes.push((name.clone(), self.named_volume_dir(name)?));
+            }
+        }
+        Ok(volumes)
+    }
+
+    // Whether a named volume holds data that a restore would overwrite
+    pub fn volume_has_data(&self, name: &str) -> Result<bool> {
+        let path = self.named_volume_dir(name)?;
+        Ok(std::fs::read_dir(&path)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false))
+    }
+
+    // List declared named volumes with their on-disk location and size
+    pub fn volume_ls(&self, ui: &dyn Reporter) -> Result<()> {
+        ui.table_header(&["VOLUME", "EXISTS", "SIZE", "PATH"]);
+
+        for name in self.config.volumes.keys() {
+            let path = self.named_volume_dir(name)?;
+            let (exists, size) = if path.is_dir() {
+                ("yes", format!("{}", HumanBytes(dir_size(&path))))
+            } else {
+                ("no", "-".to_string())
+            };
+            ui.table_row(
+                &[name, exists, &size, &path.to_string_lossy()],
+                Some(if exists == "yes" { "green" } else { "yellow" }),
+            );
+        }
+
+        Ok(())
+    }
+
+    // Show where a named volume lives and which services mount it
+    pub fn volume_inspect(&self, name: &str, ui: &dyn Reporter) -> Result<()> {
+        if !self.is_named_volume(name) {
+            return Err(ComposeError::VolumeNotFound(name.to_string()));
+        }
+
+        let path = self.named_volume_dir(name)?;
+        ui.info(&format!("Volume: {}", name));
+        ui.info(&format!("Path: {}", path.display()));
+
+        if path.is_dir() {
+            ui.info(&format!("Size: {}", HumanBytes(dir_size(&path))));
+        } else {
+            ui.warning("Directory does not exist yet (created on first up)");
+        }
+
+        let services: Vec<&String> = self
+            .config
+            .services
+            .iter()
+            .filter(|(_, service)| {
+                service
+                    .volumes
+                    .iter()
+                    .any(|v| v.split(':').next() == Some(name))
+            })
+            .map(|(service_name, _)| service_name)
+            .collect();
+
+        if services.is_empty() {
+            ui.info("Mounted by: (no services)");
+        } else {
+            let services: Vec<&str> = services.iter().map(|s| s.as_str()).collect();
+            ui.info(&format!("Mounted by: {}", services.join(", ")));
+        }
+
+        Ok(())
+    }
+
+    // Archive a named volume's directory into a .tar.gz file
+    pub async fn volume_backup(&self, name: &str, archive: &Path, ui: &dyn Reporter) -> Result<()> {
+        if !self.is_named_volume(name) {
+            return Err(ComposeError::VolumeNotFound(name.to_string()));
+        }
+
+        let path = self.named_volume_dir(name)?;
+        if !path.is_dir() {
+            return Err(ComposeError::VolumeEmpty {
+                name: name.to_string(),
+                path,
+            });
+        }
+
+        let mut cmd = AsyncCommand::new("tar");
+        cmd.arg("-czf").arg(archive).arg("-C").arg(&path).arg(".");
+        let output = run_logged(&mut cmd, ui).await?;
+
+        if !output.status.success() {
+            return Err(ComposeError::ContainerCommandFailed {
+                action: "back up volume".to_string(),
+                target: name.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
+
+        ui.success(&format!(
+            "Backed up volume '{}' to {}",
+            name,
+            archive.display()
+        ));
+        Ok(())
+    }
+
+    // Unpack a .tar.gz archive into a named volume's directory
+    pub async fn volume_restore(
+        &self,
+        name: &str,
+        archive: &Path,
+        force: bool,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        if !self.is_named_volume(name) {
+            return Err(ComposeError::VolumeNotFound(name.to_string()));
+        }
+        if !archive.is_file() {
+            return Err(ComposeError::ArchiveNotFound(archive.to_path_buf()));
+        }
+
+        let path = self.named_volume_dir(name)?;
+        if !force && self.volume_has_data(name)? {
+            return Err(ComposeError::VolumeNotEmpty(name.to_string()));
+        }
+
+        // Extract next to the volume and only swap it in once tar succeeded, so a
+        // corrupt archive leaves the existing data alone
+        let staging = path.with_file_name(format!(".{}.restore-{}", name, std::process::id()));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(&staging)?;
+
+        let mut cmd = AsyncCommand::new("tar");
+        cmd.arg("-xzf").arg(archive).arg("-C").arg(&staging);
+        let output = run_logged(&mut cmd, ui).await?;
+
+        if !output.status.success() {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(ComposeError::ContainerCommandFailed {
+                action: "restore volume".to_string(),
+                target: name.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
+
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::rename(&staging, &path)?;
+
+        ui.success(&format!(
+            "Restored volume '{}' from {}",
+            name,
+            archive.display()
+        ));
+        Ok(())
+    }
+
+    // Create every network referenced by a service (existing networks are left alone)
+    async fn initialize_networks(&self, ui: &dyn Reporter) -> Result<()> {
+        let mut referenced: Vec<&String> = self
+            .config
+            .services
+            .values()
+            .flat_map(|service| service.networks.keys())
+            .filter(|name| name.as_str() != "default")
+            // External networks are managed outside this project
+            .filter(|name| !self.config.networks.get(*name).is_some_and(|n| n.external))
+            .collect();
+        referenced.sort();
+        referenced.dedup();
+
+        for name in referenced {
+            let mut cmd = self.container_command();
+            cmd.args(["network", "create"]);
+
+            if let Some(subnet) = self.config.networks.get(name).and_then(|n| n.subnet()) {
+                cmd.args(["--subnet", subnet]);
+            }
+            cmd.arg(name);
+
+            let output = run_logged(&mut cmd, ui).await?;
+            if !output.status.success() {
+                let error_msg = String::from_utf8_lossy(&output.stderr);
+                if !error_msg.contains("already exists") {
+                    return Err(ComposeError::ContainerCommandFailed {
+                        action: "create network".to_string(),
+                        target: name.to_string(),
+                        stderr: error_msg.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Get the names of networks that currently exist in the container framework
+    async fn get_existing_networks(&self) -> Result<Vec<String>> {
+        let output = self
+            .container_command()
+            .args(["network", "list"])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1) // Skip header line
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|name| name.to_string())
+            .collect())
+    }
 
-        let volumes_dir = std::path::Path::new(&home_dir)
-            .join(".container-compose")
-            .join("volumes")
-            .join(volume_name);
+    // Get the services attached to a network, in declaration order
+    fn services_on_network(&self, network: &str) -> Vec<&str> {
+        self.config
+            .services
+            .iter()
+            .filter(|(_, service)| service.networks.contains_key(network))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
 
-        // Create the directory if it doesn't exist
-        std::fs::create_dir_all(&volumes_dir)?;
+    // List declared networks with their driver and whether they exist
+    pub async fn network_ls(&self, ui: &dyn Reporter) -> Result<()> {
+        let existing = self.get_existing_networks().await?;
+
+        ui.table_header(&["NETWORK", "EXISTS", "DRIVER", "EXTERNAL"]);
+
+        for name in self.config.networks.keys() {
+            let network = &self.config.networks[name];
+            let exists = existing.contains(name);
+            ui.table_row(
+                &[
+                    name,
+                    if exists { "yes" } else { "no" },
+                    &network.driver,
+                    if network.external { "yes" } else { "no" },
+                ],
+                Some(if exists { "green" } else { "yellow" }),
+            );
+        }
 
-        Ok(volumes_dir.to_string_lossy().to_string())
+        Ok(())
     }
 
-    // Initialize named volumes (create directories)
-    pub async fn initialize_volumes(&self) -> Result<()> {
-        for volume_name in self.config.volumes.keys() {
-            let _volume_path = self.get_named_volume_path(volume_name)?;
-            // Volume directory is created in get_named_volume_path
+    // Show the framework's view of a network plus the services attached to it
+    pub async fn network_inspect(&self, name: &str, ui: &dyn Reporter) -> Result<()> {
+        if !self.config.networks.contains_key(name) {
+            return Err(ComposeError::NetworkNotFound(name.to_string()));
+        }
+
+        let output = run_logged(
+            self.container_command().args(["network", "inspect", name]),
+            ui,
+        )
+        .await?;
+
+        if output.status.success() {
+            ui.output(String::from_utf8_lossy(&output.stdout).trim_end());
+        } else {
+            ui.warning(&format!("Network '{}' does not exist yet", name));
+        }
+
+        let services = self.services_on_network(name);
+        if services.is_empty() {
+            ui.info("Attached services: (none)");
+        } else {
+            ui.info(&format!("Attached services: {}", services.join(", ")));
         }
+
         Ok(())
     }
 
-    // Execute a command in a running container
+    // Execute a command in a running container, from `workdir` or the service's working_dir
     pub async fn exec(
         &self,
         service_name: &str,
         command: &[String],
-        ui: &UI,
-        verbose: bool,
+        options: &ExecOptions,
+        ui: &dyn Reporter,
     ) -> Result<()> {
         // Check if service exists in config
-        if !self.config.services.contains_key(service_name) {
-            return Err(anyhow::anyhow!("Service '{}' not found", service_name));
+        let Some(service) = self.config.services.get(service_name) else {
+            return Err(ComposeError::ServiceNotFound(service_name.to_string()));
+        };
+
+        // Execute command using Apple's container framework
+        let mut cmd = self.container_command();
+        cmd.arg("exec");
+
+        if options.interactive {
+            cmd.arg("--interactive");
+        }
+        if options.tty {
+            cmd.arg("--tty");
         }
 
-        if verbose {
-            ui.command(&format!(
-                "container exec {} {}",
-                service_name,
-                command.join(" ")
-            ));
+        // Run from the service's working directory unless told otherwise
+        if let Some(workdir) = options
+            .workdir
+            .as_deref()
+            .or(service.working_dir.as_deref())
+        {
+            cmd.args(["--workdir", workdir]);
         }
 
-        // Execute command using Apple's container framework
-        let mut cmd = AsyncCommand::new("container");
-        cmd.args(&["exec", service_name]);
+        // A scaled service runs the command in its first replica
+        let instances = self.get_service_instances().await?;
+        cmd.arg(&self.containers_of(service_name, &instances)[0]);
         cmd.args(command);
 
         // Inherit stdin, stdout, stderr for interactive usage
@@ -713,16 +3384,380 @@ impl ContainerManager {
         cmd.stdout(Stdio::inherit());
         cmd.stderr(Stdio::inherit());
 
-        let status = cmd.status().await?;
+        ui.command(&cmd);
+        let mut child = cmd.spawn()?;
+        let status = if options.tty {
+            wait_forwarding_signals(&mut child).await?
+        } else {
+            child.wait().await?
+        };
 
         if !status.success() {
-            return Err(anyhow::anyhow!(
-                "Command failed in container '{}' with exit code: {}",
-                service_name,
-                status.code().unwrap_or(-1)
-            ));
+            return Err(ComposeError::ExecFailed {
+                service: service_name.to_string(),
+                exit_code: status.code().unwrap_or(-1),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Run a one-off command in a new container for the service, with the
+    // overrides from `options` layered on top of its definition
+    pub async fn run(
+        &self,
+        service_name: &str,
+        command: &[String],
+        options: &RunOptions,
+        ui: &dyn Reporter,
+    ) -> Result<()> {
+        let Some(service) = self.config.services.get(service_name) else {
+            return Err(ComposeError::ServiceNotFound(service_name.to_string()));
+        };
+
+        let host_env: HashMap<String, String> = std::env::vars().collect();
+        let mut service = service.interpolated(service_name, &host_env)?;
+        for env in &options.env {
+            let key = env.split('=').next().unwrap_or_default();
+            service
+                .environment
+                .retain(|existing| existing.split('=').next() != Some(key));
+            service.environment.push(env.clone());
+        }
+        if let Some(workdir) = &options.workdir {
+            service.working_dir = Some(workdir.clone());
+        }
+        if !command.is_empty() {
+            service.command = Some(command.to_vec());
+        }
+
+        self.initialize_volumes(ui).await?;
+        self.initialize_networks(ui).await?;
+
+        let container = format!(
+            "{}-{}-run-{}",
+            self.project_name,
+            service_name,
+            std::process::id()
+        );
+        let mut cmd = self.container_command();
+        cmd.args(["run", "--name", &container, "--interactive"]);
+
+        // Labeled as the project's, but marked one-off so it never counts as one
+        // of the service's replicas or an orphan
+        cmd.args([
+            "--label",
+            &format!("{}={}", PROJECT_LABEL, self.project_name),
+        ]);
+        cmd.args(["--label", &format!("{}={}", SERVICE_LABEL, service_name)]);
+        cmd.args(["--label", &format!("{}=true", ONEOFF_LABEL)]);
+        if !options.no_tty {
+            cmd.arg("--tty");
+        }
+        if options.rm {
+            cmd.arg("--rm");
+        }
+        if let Some(user) = &options.user {
+            cmd.args(["--user", user]);
+        }
+        if options.service_ports {
+            for port in &service.ports {
+                cmd.args(["--publish", port]);
+            }
+        }
+        self.add_service_options(&mut cmd, &service, None)?;
+        add_image_and_command(&mut cmd, &service);
+
+        // Inherit stdin, stdout, stderr for interactive usage
+        cmd.stdin(Stdio::inherit());
+        cmd.stdout(Stdio::inherit());
+        cmd.stderr(Stdio::inherit());
+
+        ui.command(&cmd);
+        let mut child = cmd.spawn()?;
+        let status = if options.no_tty {
+            child.wait().await?
+        } else {
+            wait_forwarding_signals(&mut child).await?
+        };
+
+        if !status.success() {
+            return Err(ComposeError::ExecFailed {
+                service: service_name.to_string(),
+                exit_code: status.code().unwrap_or(-1),
+            });
         }
 
         Ok(())
     }
 }
+
+// Wait for an interactive child without letting signals meant for it kill us.
+// Ctrl-C already reaches the child, which shares our terminal's foreground
+// process group, so SIGINT is only absorbed here; SIGTERM and SIGHUP sent to us
+// are forwarded, as is SIGWINCH so the child picks up the new terminal size.
+async fn wait_forwarding_signals(child: &mut Child) -> Result<ExitStatus> {
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut resize = signal(SignalKind::window_change())?;
+
+    loop {
+        let forward = tokio::select! {
+            status = child.wait() => return Ok(status?),
+            _ = interrupt.recv() => None,
+            _ = terminate.recv() => Some(libc::SIGTERM),
+            _ = hangup.recv() => Some(libc::SIGHUP),
+            _ = resize.recv() => Some(libc::SIGWINCH),
+        };
+
+        if let (Some(sig), Some(pid)) = (forward, child.id()) {
+            // The pid belongs to our own child, which has not been reaped yet
+            unsafe {
+                libc::kill(pid as libc::pid_t, sig);
+            }
+        }
+    }
+}
+
+// Append the image, preceded by any entrypoint override and followed by the
+// remaining entrypoint arguments and the command
+fn add_image_and_command(cmd: &mut AsyncCommand, service: &Service) {
+    // Override the entrypoint executable; any further entrypoint arguments go
+    // before the command, and an empty list clears the image's entrypoint
+    let entrypoint = service.entrypoint.as_deref().unwrap_or_default();
+    match entrypoint.first() {
+        Some(executable) => {
+            cmd.args(["--entrypoint", executable]);
+        }
+        None if service.entrypoint.is_some() => {
+            cmd.args(["--entrypoint", ""]);
+        }
+        None => {}
+    }
+
+    // Add the image
+    cmd.arg(&service.image);
+    cmd.args(entrypoint.iter().skip(1));
+
+    // Add command if specified
+    if let Some(command) = &service.command {
+        cmd.args(command);
+    }
+}
+
+// Run a command to completion, echoing its argv (-v) and captured output (-vv)
+async fn run_logged(cmd: &mut AsyncCommand, ui: &dyn Reporter) -> Result<Output> {
+    ui.command(cmd);
+    let output = cmd.output().await?;
+    ui.command_output(&output);
+    Ok(output)
+}
+
+// Like `run_logged`, but hands every stdout and stderr line to `on_line` as it
+// arrives. Lines are also split on `\r`, which progress output uses to redraw.
+async fn run_streamed(
+    cmd: &mut AsyncCommand,
+    ui: &dyn Reporter,
+    mut on_line: impl FnMut(&str),
+) -> Result<Output> {
+    ui.command(cmd);
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    let mut child = cmd.spawn()?;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let stdout = child.stdout.take().map(|r| capture_lines(r, tx.clone()));
+    let stderr = child.stderr.take().map(|r| capture_lines(r, tx.clone()));
+    drop(tx);
+
+    while let Some(line) = rx.recv().await {
+        on_line(&line);
+    }
+
+    let status = child.wait().await?;
+    let output = Output {
+        status,
+        stdout: match stdout {
+            Some(task) => task.await?,
+            None => Vec::new(),
+        },
+        stderr: match stderr {
+            Some(task) => task.await?,
+            None => Vec::new(),
+        },
+    };
+    ui.command_output(&output);
+    Ok(output)
+}
+
+// Send each non-empty line of a stream to `tx`, returning everything read
+fn capture_lines<R>(mut reader: R, tx: mpsc::UnboundedSender<String>) -> JoinHandle<Vec<u8>>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut captured = Vec::new();
+        let mut line = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        while let Ok(n) = reader.read(&mut chunk).await {
+            if n == 0 {
+                break;
+            }
+            captured.extend_from_slice(&chunk[..n]);
+            for &byte in &chunk[..n] {
+                if byte == b'\n' || byte == b'\r' {
+                    if !line.is_empty() {
+                        let _ = tx.send(String::from_utf8_lossy(&line).trim().to_string());
+                        line.clear();
+                    }
+                } else {
+                    line.push(byte);
+                }
+            }
+        }
+        if !line.is_empty() {
+            let _ = tx.send(String::from_utf8_lossy(&line).trim().to_string());
+        }
+
+        captured
+    })
+}
+
+// Find a progress percentage such as `45%` or `12.5%` in a line of output
+fn parse_percent(line: &str) -> Option<u64> {
+    line.split(|c: char| c.is_whitespace() || c == '(' || c == '[')
+        .filter_map(|word| word.strip_suffix('%'))
+        .filter_map(|number| number.parse::<f64>().ok())
+        .find(|percent| (0.0..=100.0).contains(percent))
+        .map(|percent| percent as u64)
+}
+
+// Whether `container run` failed because the requested name is already taken
+fn is_name_conflict(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    stderr.contains("already exists") || stderr.contains("already in use")
+}
+
+// Forward each line of a log stream to the aggregator, tagged with its service
+fn forward_lines<R>(service_name: &str, reader: R, tx: mpsc::UnboundedSender<(String, String)>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let service_name = service_name.to_string();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if tx.send((service_name.clone(), line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Image tag of a service that only has a build section: `<project>-<service>`
+fn default_image(project_name: &str, service_name: &str) -> String {
+    format!("{}-{}", project_name, service_name)
+}
+
+// Seconds since the Unix epoch
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Find the first value stored under `key` anywhere in a JSON document
+fn find_json_key<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|v| find_json_key(v, key))),
+        serde_json::Value::Array(items) => items.iter().find_map(|v| find_json_key(v, key)),
+        _ => None,
+    }
+}
+
+// Resolve `ps --filter status=<running|stopped>` options into the state to keep
+fn ps_state_filter(filters: &[String]) -> Result<Option<ServiceState>> {
+    let mut state = None;
+
+    for filter in filters {
+        let Some((key, value)) = filter.split_once('=') else {
+            return Err(ComposeError::InvalidFilter(format!(
+                "Invalid filter '{}': expected KEY=VALUE",
+                filter
+            )));
+        };
+        if key != "status" {
+            return Err(ComposeError::InvalidFilter(format!(
+                "Unsupported filter key '{}'",
+                key
+            )));
+        }
+        state = Some(match value {
+            "running" => ServiceState::Running,
+            "stopped" => ServiceState::Created,
+            _ => {
+                return Err(ComposeError::InvalidFilter(format!(
+                    "Unsupported status '{}' (expected running or stopped)",
+                    value
+                )));
+            }
+        });
+    }
+
+    Ok(state)
+}
+
+// Substitute `{{.Field}}` placeholders, rejecting any field that isn't known
+fn render_template(template: &str, fields: &[(&str, &str)]) -> Result<String> {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| {
+            ComposeError::InvalidTemplate("Unclosed '{{' in format template".to_string())
+        })?;
+
+        let token = after[..end].trim();
+        let value = token
+            .strip_prefix('.')
+            .and_then(|name| fields.iter().find(|(field, _)| *field == name))
+            .map(|(_, value)| *value)
+            .ok_or_else(|| {
+                let valid: Vec<String> = fields.iter().map(|(f, _)| format!(".{}", f)).collect();
+                ComposeError::InvalidTemplate(format!(
+                    "Unknown placeholder '{{{{{}}}}}' (valid: {})",
+                    token,
+                    valid.join(", ")
+                ))
+            })?;
+
+        output.push_str(value);
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+// Total size in bytes of all files under a directory (symlinks are not followed)
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
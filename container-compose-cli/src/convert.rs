@@ -0,0 +1,149 @@
+use crate::config::{
+    Build, ConfigFile, ContainerComposeConfig, HealthCheck, Network, Service, ServiceConfig,
+    ServiceNetwork,
+};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap};
+
+// A docker-compose file, limited to the fields container-compose understands
+#[derive(Debug, Serialize)]
+pub struct DockerCompose {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub services: IndexMap<String, DockerService>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub volumes: IndexMap<String, DockerVolume>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub networks: IndexMap<String, Network>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub configs: IndexMap<String, ConfigFile>,
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DockerService {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub environment: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tty: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdin_open: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub networks: BTreeMap<String, ServiceNetwork>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub configs: Vec<ServiceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<HealthCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<Build>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DockerVolume {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub driver: String,
+}
+
+impl DockerCompose {
+    // Map a loaded config onto the docker-compose schema, with warnings for
+    // anything that has no Docker equivalent
+    pub fn from_config(config: &ContainerComposeConfig) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+
+        let services = config
+            .services
+            .iter()
+            .map(|(name, service)| {
+                (
+                    name.clone(),
+                    DockerService::from_service(name, service, &mut warnings),
+                )
+            })
+            .collect();
+
+        let compose = Self {
+            name: config.name.clone(),
+            services,
+            volumes: config
+                .volumes
+                .iter()
+                .map(|(name, volume)| {
+                    (
+                        name.clone(),
+                        DockerVolume {
+                            driver: volume.driver.clone(),
+                        },
+                    )
+                })
+                .collect(),
+            networks: config.networks.clone(),
+            configs: config.configs.clone(),
+            extensions: sorted(&config.extensions),
+        };
+
+        (compose, warnings)
+    }
+}
+
+impl DockerService {
+    fn from_service(name: &str, service: &Service, warnings: &mut Vec<String>) -> Self {
+        if let Some(timeout) = service.start_timeout {
+            warnings.push(format!(
+                "Service '{}': start_timeout ({}s) has no docker-compose equivalent and was dropped",
+                name, timeout
+            ));
+        }
+
+        Self {
+            // Build-only services have no image; docker-compose names it after the build
+            image: (!service.image.is_empty()).then(|| service.image.clone()),
+            ports: service.ports.clone(),
+            volumes: service.volumes.clone(),
+            environment: service.environment.clone(),
+            depends_on: service.depends_on.clone(),
+            command: service.command.clone(),
+            entrypoint: service.entrypoint.clone(),
+            init: service.init,
+            tty: service.tty,
+            stdin_open: service.stdin_open,
+            working_dir: service.working_dir.clone(),
+            container_name: service.container_name.clone(),
+            networks: sorted(&service.networks),
+            configs: service.configs.clone(),
+            healthcheck: service.healthcheck.clone(),
+            build: service.build.clone(),
+            profiles: service.profiles.clone(),
+            extensions: sorted(&service.extensions),
+        }
+    }
+}
+
+// Copy a map into key order so the generated file is stable
+fn sorted<V: Clone>(map: &HashMap<String, V>) -> BTreeMap<String, V> {
+    map.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
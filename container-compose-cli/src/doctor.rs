@@ -0,0 +1,130 @@
+use crate::container::ContainerManager;
+use crate::ui::Reporter;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as AsyncCommand;
+use tokio::time::{Duration, timeout};
+
+// Environment details collected by `container-compose doctor`
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub version: String,
+    pub container_binary: Option<String>,
+    pub container_version: Option<String>,
+    pub container_reachable: bool,
+    pub compose_file: String,
+    pub compose_file_exists: bool,
+    pub volumes_dir: Option<String>,
+}
+
+impl DoctorReport {
+    // Gather the report for the given compose file path, volumes override and
+    // `container` binary (a bare name is looked up in PATH)
+    pub async fn collect(compose_file: &str, volumes_dir: Option<&Path>, binary: &Path) -> Self {
+        let container_binary = if binary.components().count() > 1 {
+            Some(resolve_path(&binary.to_string_lossy())).filter(|path| path.is_file())
+        } else {
+            find_in_path(binary)
+        };
+
+        let container_version = match &container_binary {
+            Some(binary) => query_version(binary).await,
+            None => None,
+        };
+
+        let compose_path = resolve_path(compose_file);
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            container_reachable: container_version.is_some(),
+            container_binary: container_binary.map(|p| p.to_string_lossy().to_string()),
+            container_version,
+            compose_file_exists: compose_path.is_file(),
+            compose_file: compose_path.to_string_lossy().to_string(),
+            volumes_dir: ContainerManager::volumes_base_dir(volumes_dir)
+                .ok()
+                .map(|p| p.to_string_lossy().to_string()),
+        }
+    }
+
+    // The environment is healthy when the container binary answers
+    pub fn is_healthy(&self) -> bool {
+        self.container_binary.is_some() && self.container_reachable
+    }
+
+    // Print the report as human-readable lines
+    pub fn print(&self, ui: &dyn Reporter) {
+        ui.info(&format!("container-compose version: {}", self.version));
+
+        match &self.container_binary {
+            Some(binary) => ui.info(&format!("container binary: {}", binary)),
+            None => ui.error("container binary: not found (see --container-binary)"),
+        }
+
+        match &self.container_version {
+            Some(version) => ui.info(&format!("container version: {}", version)),
+            None if self.container_binary.is_some() => {
+                ui.error("container version: binary did not respond to --version")
+            }
+            None => {}
+        }
+
+        if self.compose_file_exists {
+            ui.info(&format!("compose file: {}", self.compose_file));
+        } else {
+            ui.warning(&format!("compose file: {} (not found)", self.compose_file));
+        }
+
+        match &self.volumes_dir {
+            Some(dir) => ui.info(&format!("volumes directory: {}", dir)),
+            None => ui.warning("volumes directory: could not determine home directory"),
+        }
+
+        if self.is_healthy() {
+            ui.success("Environment looks good");
+        } else {
+            ui.error("The container binary is missing or unresponsive");
+        }
+    }
+}
+
+// Locate an executable by searching the PATH environment variable
+fn find_in_path(binary: &Path) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+// Run `<binary> --version`, giving up if it hangs
+async fn query_version(binary: &Path) -> Option<String> {
+    let output = timeout(
+        Duration::from_secs(5),
+        AsyncCommand::new(binary)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+// Make a possibly relative path absolute against the current directory
+fn resolve_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::path::PathBuf;
+
+// Everything that can go wrong loading a config or managing its containers, so
+// callers can react to specific failures instead of matching on message text
+#[derive(Debug)]
+pub enum ComposeError {
+    // A name that isn't defined in the config
+    ServiceNotFound(String),
+    VolumeNotFound(String),
+    NetworkNotFound(String),
+    ConfigNotFound(String),
+
+    // The config file could not be read, parsed or resolved
+    ConfigRead {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    InvalidExtends(String),
+    InvalidInclude(String),
+    CircularDependency(String),
+    Interpolation {
+        service: String,
+        field: String,
+        reason: String,
+    },
+
+    // A volume mount that can't be set up
+    VolumeSourceMissing {
+        source: String,
+        resolved: String,
+    },
+    InvalidVolume {
+        volume: String,
+        reason: String,
+    },
+    AnonymousVolume(String),
+    NoHomeDirectory,
+    // Every volume problem found before `up` started anything, by service
+    InvalidMounts(Vec<(String, ComposeError)>),
+
+    // Named volume data that isn't in the state an operation needs
+    VolumeEmpty {
+        name: String,
+        path: PathBuf,
+    },
+    VolumeNotEmpty(String),
+    ArchiveNotFound(PathBuf),
+
+    // A `container` (or `tar`) invocation that reported failure, e.g.
+    // action "pull image" and target "nginx:latest"
+    ContainerCommandFailed {
+        action: String,
+        target: String,
+        stderr: String,
+    },
+    // `container run` refused a name that another container already has
+    NameConflict {
+        service: String,
+        container: String,
+    },
+    StartTimeout {
+        service: String,
+        seconds: u64,
+    },
+    // A service whose container exited on its own instead of staying up
+    ServiceExited {
+        service: String,
+        exit_code: i32,
+    },
+    ExecFailed {
+        service: String,
+        exit_code: i32,
+    },
+    Unhealthy(Vec<String>),
+    HealthTimeout {
+        services: Vec<String>,
+        seconds: u64,
+    },
+    // A service skipped because one it depends on failed
+    DependencyFailed(String),
+    // Every failure of a `--keep-going` run, by service, e.g. action "start"
+    ServicesFailed {
+        action: String,
+        failures: Vec<(String, ComposeError)>,
+    },
+    InvalidScale {
+        service: String,
+        replicas: usize,
+        reason: String,
+    },
+
+    // Bad command line input, such as a `--filter` or `--format` template
+    InvalidFilter(String),
+    InvalidTemplate(String),
+
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ComposeError>;
+
+// Display, Error and the From conversions are written out by hand rather than
+// derived with thiserror, which the offline build has no copy of; they map
+// one-to-one onto #[error(...)], #[source] and #[from] if it is ever added
+impl fmt::Display for ComposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComposeError::ServiceNotFound(name) => write!(f, "Service '{}' not found", name),
+            ComposeError::VolumeNotFound(name) => write!(f, "Volume '{}' not found", name),
+            ComposeError::NetworkNotFound(name) => write!(f, "Network '{}' not found", name),
+            ComposeError::ConfigNotFound(name) => write!(f, "Config '{}' not found", name),
+            ComposeError::ConfigRead { path, source } => {
+                write!(f, "Failed to read '{}': {}", path.display(), source)
+            }
+            ComposeError::InvalidExtends(reason) | ComposeError::InvalidInclude(reason) => {
+                write!(f, "{}", reason)
+            }
+            ComposeError::CircularDependency(service) => {
+                write!(f, "Circular dependency detected involving '{}'", service)
+            }
+            ComposeError::Interpolation {
+                service,
+                field,
+                reason,
+            } => write!(f, "Service '{}' {}: {}", service, field, reason),
+            ComposeError::VolumeSourceMissing { source, resolved } => write!(
+                f,
+                "Volume mount source path does not exist: {} (resolved to: {})",
+                source, resolved
+            ),
+            ComposeError::InvalidVolume { volume, reason } => {
+                write!(f, "Invalid volume '{}': {}", volume, reason)
+            }
+            ComposeError::AnonymousVolume(volume) => {
+                write!(f, "Anonymous volumes are not supported: {}", volume)
+            }
+            ComposeError::NoHomeDirectory => write!(
+                f,
+                "Could not find a home directory for named volume data; set HOME or CONTAINER_COMPOSE_HOME, or pass --volumes-dir"
+            ),
+            ComposeError::InvalidMounts(problems) => {
+                write!(f, "{} volume mount(s) can't be set up:", problems.len())?;
+                for (service, problem) in problems {
+                    write!(f, "\n  Service '{}': {}", service, problem)?;
+                }
+                Ok(())
+            }
+            ComposeError::VolumeEmpty { name, path } => write!(
+                f,
+                "Volume '{}' has no data yet ({} does not exist)",
+                name,
+                path.display()
+            ),
+            ComposeError::VolumeNotEmpty(name) => write!(
+                f,
+                "Volume '{}' is not empty; use --force to overwrite it",
+                name
+            ),
+            ComposeError::ArchiveNotFound(path) => {
+                write!(f, "Archive not found: {}", path.display())
+            }
+            ComposeError::ContainerCommandFailed {
+                action,
+                target,
+                stderr,
+            } => write!(f, "Failed to {} '{}': {}", action, target, stderr.trim()),
+            ComposeError::NameConflict { service, container } => write!(
+                f,
+                "Cannot start service '{}': a container named '{}' already exists and isn't part of this project.\n\
+                 Remove it with `container rm {}`, pick another project name with `-p <other-name>`, \
+                 or give the service an explicit `container_name`",
+                service, container, container
+            ),
+            ComposeError::StartTimeout { service, seconds } => {
+                write!(f, "Service '{}' did not start within {}s", service, seconds)
+            }
+            ComposeError::ServiceExited { service, exit_code } => write!(
+                f,
+                "Service '{}' exited with code {} right after starting",
+                service, exit_code
+            ),
+            ComposeError::ExecFailed { service, exit_code } => write!(
+                f,
+                "Command failed in container '{}' with exit code: {}",
+                service, exit_code
+            ),
+            ComposeError::Unhealthy(services) => write!(
+                f,
+                "Service(s) failed to become healthy: {}",
+                services.join(", ")
+            ),
+            ComposeError::HealthTimeout { services, seconds } => write!(
+                f,
+                "Service(s) not healthy within {}s: {}",
+                seconds,
+                services.join(", ")
+            ),
+            ComposeError::DependencyFailed(dependency) => {
+                write!(f, "Skipped because dependency '{}' failed", dependency)
+            }
+            ComposeError::ServicesFailed { action, failures } => {
+                write!(f, "Failed to {} {} service(s):", action, failures.len())?;
+                for (service, failure) in failures {
+                    write!(f, "\n  Service '{}': {}", service, failure)?;
+                }
+                Ok(())
+            }
+            ComposeError::InvalidScale {
+                service,
+                replicas,
+                reason,
+            } => write!(
+                f,
+                "Cannot scale service '{}' to {}: {}",
+                service, replicas, reason
+            ),
+            ComposeError::InvalidFilter(reason) | ComposeError::InvalidTemplate(reason) => {
+                write!(f, "{}", reason)
+            }
+            ComposeError::Io(e) => write!(f, "{}", e),
+            ComposeError::Json(e) => write!(f, "{}", e),
+            ComposeError::Yaml(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ComposeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ComposeError::ConfigRead { source, .. } | ComposeError::Io(source) => Some(source),
+            ComposeError::Json(e) => Some(e),
+            ComposeError::Yaml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ComposeError {
+    fn from(e: std::io::Error) -> Self {
+        ComposeError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ComposeError {
+    fn from(e: serde_json::Error) -> Self {
+        ComposeError::Json(e)
+    }
+}
+
+impl From<serde_yaml::Error> for ComposeError {
+    fn from(e: serde_yaml::Error) -> Self {
+        ComposeError::Yaml(e)
+    }
+}
+
+impl From<tokio::task::JoinError> for ComposeError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        ComposeError::Io(std::io::Error::other(e))
+    }
+}
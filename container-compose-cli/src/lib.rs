@@ -0,0 +1,15 @@
+// The config loading and container management behind the `container-compose`
+// CLI, usable on its own: load a `ContainerComposeConfig`, hand it to a
+// `ContainerManager` and drive it with any `Reporter`.
+
+pub mod config;
+pub mod container;
+pub mod convert;
+pub mod doctor;
+pub mod error;
+pub mod ui;
+
+pub use config::ContainerComposeConfig;
+pub use container::ContainerManager;
+pub use error::{ComposeError, Result};
+pub use ui::{CollectingReporter, JsonReporter, NullReporter, Reporter, UI};
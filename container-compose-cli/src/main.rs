@@ -1,35 +1,86 @@
-#![allow(clippy::uninlined_format_args)]
-#![allow(clippy::needless_borrows_for_generic_args)]
-#![allow(clippy::unnecessary_unwrap)]
-#![allow(clippy::len_zero)]
-#![allow(clippy::manual_strip)]
-
 mod cli;
-mod config;
-mod container;
-mod ui;
 
 use anyhow::Result;
-use cli::{Cli, Commands};
-use config::ContainerComposeConfig;
-use container::ContainerManager;
-use ui::UI;
+use cli::{Cli, Commands, LogTail, NetworkCommands, VolumeCommands};
+use container_compose::config::{
+    ContainerComposeConfig, ValidationError, image_without_digest, is_valid_project_name,
+};
+use container_compose::container::{
+    BuildOptions, ContainerManager, DEFAULT_LOG_TAIL, ExecOptions, LogOptions, PsOptions,
+    RecreatePolicy, RunOptions, StopOptions, UpOptions,
+};
+use container_compose::convert::DockerCompose;
+use container_compose::doctor::DoctorReport;
+use container_compose::error::ComposeError;
+use container_compose::ui::{JsonReporter, OutputFormat, Reporter, UI};
+use std::io::IsTerminal;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Parse command line arguments
     let args = Cli::parse_args();
 
-    // Create UI instance
-    let ui = UI::new();
+    // Human-readable output unless --json asks for a stream of events
+    let reporter: Box<dyn Reporter> = if args.json {
+        Box::new(JsonReporter::new(args.verbose))
+    } else {
+        Box::new(UI::new(args.ansi, args.verbose))
+    };
+    let ui = reporter.as_ref();
+
+    // Doctor runs before loading the config so it works even when the file is broken
+    if let Commands::Doctor = args.command {
+        let report = DoctorReport::collect(
+            &args.file,
+            args.volumes_dir.as_deref(),
+            &args.container_binary,
+        )
+        .await;
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            ui.header("Container Compose Doctor");
+            report.print(ui);
+        }
+        if !report.is_healthy() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Config prints machine-consumable output, so it skips the header
+    if let Commands::Config {
+        format,
+        project_name,
+        resolve_image_digests,
+    } = args.command
+    {
+        return run_config(&args, format, project_name, resolve_image_digests, ui).await;
+    }
+
+    // Convert writes a docker-compose file to stdout
+    if let Commands::Convert = args.command {
+        return run_convert(&args, ui);
+    }
+
+    // Output meant for scripts only carries the requested data
+    let scripted = args.command.is_scripted();
 
     // Print header
-    ui.header("Container Compose v0.1.0");
-    ui.info(&format!("Using config file: {}", args.file));
+    if !scripted && !args.json {
+        ui.header("Container Compose v0.1.0");
+        ui.info(&format!("Using config file: {}", args.file));
+    }
 
     // Load and validate configuration
-    let config = match ContainerComposeConfig::from_file(&args.file) {
+    let mut config = match ContainerComposeConfig::from_file(&args.file) {
         Ok(config) => {
-            ui.success("Configuration loaded successfully");
+            if !scripted {
+                ui.success("Configuration loaded successfully");
+                for warning in config.warnings() {
+                    ui.warning(&warning);
+                }
+            }
             config
         }
         Err(e) => {
@@ -38,110 +89,547 @@ async fn main() -> Result<()> {
         }
     };
 
-    if let Err(e) = config.validate() {
-        ui.error(&format!("Configuration validation failed: {e}"));
+    if let Some(name) = &args.project_name
+        && !is_valid_project_name(name)
+    {
+        ui.error(&format!("Invalid project name '{name}'"));
         std::process::exit(1);
     }
 
+    if let Err(errors) = validate(&config, &args) {
+        ui.error(&format!(
+            "Configuration validation failed with {} error(s):",
+            errors.len()
+        ));
+        for error in &errors {
+            ui.error(&format!("  {error}"));
+        }
+        std::process::exit(1);
+    }
+    config.apply_profiles(&args.profiles);
+
+    // The exit-code service must exist before anything is started
+    if let Commands::Up {
+        exit_code_from: Some(service),
+        ..
+    } = &args.command
+        && !config.services.contains_key(service)
+    {
+        ui.error(&format!(
+            "Service '{service}' given to --exit-code-from not found"
+        ));
+        std::process::exit(1);
+    }
+
+    // Most commands only care whether -v was given; the UI handles -vv itself
+    let verbose = args.verbose > 0;
+
     // Create container manager
-    let mut manager = ContainerManager::new(config);
+    let project_name = config.project_name(args.project_name.as_deref(), &args.project_directory());
+    let mut manager = ContainerManager::new(
+        config,
+        project_name,
+        args.project_directory(),
+        args.volumes_dir.clone(),
+        args.container_binary.clone(),
+    );
+
+    // Exit code to propagate from a service, if a command reports one
+    let mut exit_code = 0;
 
     // Handle different commands
     let result = match args.command {
         Commands::Up {
             detach,
             force_recreate,
+            no_recreate,
+            abort_on_container_exit,
+            exit_code_from,
+            start_timeout,
+            wait,
+            wait_timeout,
+            confirm_running,
+            remove_orphans,
+            scale,
+            keep_going,
         } => {
             ui.separator();
             ui.info(&format!(
                 "Starting services (detach: {detach}, force_recreate: {force_recreate})"
             ));
-            manager.up(&ui, args.verbose).await
+            let options = UpOptions {
+                start_timeout,
+                wait_timeout,
+                wait,
+                confirm_running,
+                remove_orphans,
+                recreate: if force_recreate {
+                    RecreatePolicy::Always
+                } else if no_recreate {
+                    RecreatePolicy::Never
+                } else {
+                    RecreatePolicy::Changed
+                },
+                scale: scale.into_iter().collect(),
+                keep_going,
+            };
+            match manager.up(&options, ui, verbose).await {
+                Ok(()) if !detach => manager
+                    .attach(
+                        abort_on_container_exit || exit_code_from.is_some(),
+                        exit_code_from.as_deref(),
+                        ui,
+                        verbose,
+                    )
+                    .await
+                    .map(|code| exit_code = code.unwrap_or(0)),
+                result => result,
+            }
         }
 
-        Commands::Down { volumes } => {
-            ui.separator();
-            ui.info(&format!("Stopping services (remove volumes: {volumes})"));
-            manager.down(&ui, args.verbose).await
+        Commands::Down {
+            volumes,
+            timeout,
+            yes,
+            remove_orphans,
+            keep_going,
+            parallel,
+        } => {
+            // Deleting volume data asks first, before anything is stopped
+            let doomed = if volumes && !yes {
+                manager.volumes_with_data()
+            } else {
+                Ok(Vec::new())
+            };
+            match doomed {
+                Err(e) => Err(e),
+                Ok(doomed)
+                    if confirm(
+                        ui,
+                        "permanently delete",
+                        &doomed
+                            .iter()
+                            .map(|(name, path)| format!("volume {} ({})", name, path.display()))
+                            .collect::<Vec<_>>(),
+                    ) == Some(false) =>
+                {
+                    ui.warning("Aborted; nothing was stopped or removed");
+                    Ok(())
+                }
+                Ok(_) => {
+                    ui.separator();
+                    ui.info(&format!("Stopping services (remove volumes: {volumes})"));
+                    let options = StopOptions {
+                        timeout,
+                        keep_going,
+                        remove_orphans,
+                        parallel,
+                    };
+                    match manager.down(&options, ui, verbose).await {
+                        Ok(()) if volumes => manager.remove_volumes(ui),
+                        result => result,
+                    }
+                }
+            }
         }
 
         Commands::Logs {
             service,
             follow,
-            tail: _,
+            tail,
+            grep,
+            no_color,
+            no_log_prefix,
         } => {
-            match service {
-                Some(service_name) => {
-                    ui.info(&format!("Showing logs for service: {service_name}"));
-                    manager.logs(&service_name, follow).await
-                }
-                None => {
-                    ui.info("Showing logs for all services");
-                    // TODO: Implement logs for all services
-                    Ok(())
-                }
+            match &service {
+                Some(service_name) => ui.info(&format!("Showing logs for service: {service_name}")),
+                None => ui.info("Showing logs for all services"),
             }
+            // Without --tail, show recent history only unless following
+            let tail = match tail {
+                Some(LogTail::All) => None,
+                Some(LogTail::Lines(lines)) => Some(lines),
+                None if follow => None,
+                None => Some(DEFAULT_LOG_TAIL),
+            };
+            let options = LogOptions {
+                follow,
+                tail,
+                no_color,
+                no_log_prefix,
+                grep,
+            };
+            manager.logs(service.as_deref(), &options, ui).await
         }
 
-        Commands::Ps => {
-            ui.separator();
-            manager.ps(&ui).await
+        Commands::Ps {
+            format,
+            services,
+            quiet,
+            filter,
+            restarts,
+            orphans,
+        } => {
+            if !scripted {
+                ui.separator();
+            }
+            let options = PsOptions {
+                format,
+                services,
+                quiet,
+                filter,
+                restarts,
+                orphans,
+            };
+            manager.ps(&options, ui).await
         }
 
-        Commands::Build { service, no_cache } => {
+        Commands::Build {
+            service,
+            no_cache,
+            pull,
+        } => {
+            ui.separator();
             ui.info(&format!(
-                "Building services (service: {service:?}, no_cache: {no_cache})"
+                "Building services (service: {service:?}, no_cache: {no_cache}, pull: {pull})"
             ));
-            // TODO: Implement build functionality
-            Ok(())
+            let options = BuildOptions { no_cache, pull };
+            manager.build(service, &options, ui).await
         }
 
         Commands::Exec {
             service,
             command,
-            interactive: _,
-            tty: _,
+            interactive,
+            tty,
+            workdir,
         } => {
+            let options = ExecOptions {
+                workdir,
+                interactive,
+                tty,
+            };
             if command.is_empty() {
                 // Default to shell if no command provided
                 let default_command = vec!["sh".to_string()];
-                manager
-                    .exec(&service, &default_command, &ui, args.verbose)
-                    .await
+                manager.exec(&service, &default_command, &options, ui).await
             } else {
-                manager.exec(&service, &command, &ui, args.verbose).await
+                manager.exec(&service, &command, &options, ui).await
             }
         }
 
-        Commands::Pull { service } => {
+        Commands::Run {
+            service,
+            command,
+            workdir,
+            user,
+            env,
+            no_tty,
+            service_ports,
+            rm,
+        } => {
+            let options = RunOptions {
+                workdir,
+                user,
+                env,
+                no_tty,
+                service_ports,
+                rm,
+            };
+            manager.run(&service, &command, &options, ui).await
+        }
+
+        Commands::Pull {
+            service,
+            with_deps,
+            keep_going,
+        } => {
             ui.separator();
             ui.info(&format!("Pulling images (service: {service:?})"));
-            manager.pull(service, &ui, args.verbose).await
+            manager.pull(service, with_deps, keep_going, ui).await
         }
 
-        Commands::Restart { service } => {
-            ui.info(&format!("Restarting services (service: {service:?})"));
-            // TODO: Implement restart functionality
-            Ok(())
+        Commands::Restart { service, cascade } => {
+            ui.separator();
+            ui.info(&format!(
+                "Restarting services (service: {service:?}, cascade: {cascade})"
+            ));
+            manager.restart(service, cascade, ui).await
         }
 
-        Commands::Stop { service } => {
+        Commands::Update { service, cascade } => {
+            ui.separator();
+            ui.info(&format!(
+                "Updating services (service: {service:?}, cascade: {cascade})"
+            ));
+            manager.update(service, cascade, ui, verbose).await
+        }
+
+        Commands::Stop {
+            service,
+            timeout,
+            remove_orphans,
+            keep_going,
+        } => {
+            ui.separator();
             ui.info(&format!("Stopping services (service: {service:?})"));
-            // TODO: Implement stop functionality
-            Ok(())
+            let options = StopOptions {
+                timeout,
+                keep_going,
+                remove_orphans,
+                ..StopOptions::default()
+            };
+            manager
+                .stop(service.as_deref(), &options, ui, verbose)
+                .await
+        }
+
+        Commands::Kill {
+            service,
+            remove_orphans,
+            keep_going,
+        } => {
+            ui.separator();
+            ui.info(&format!("Killing services (service: {service:?})"));
+            // A zero timeout skips straight to `container kill`
+            let options = StopOptions {
+                timeout: 0,
+                keep_going,
+                remove_orphans,
+                ..StopOptions::default()
+            };
+            manager
+                .stop(service.as_deref(), &options, ui, verbose)
+                .await
         }
 
         Commands::Start { service } => {
-            ui.info(&format!("Starting services (service: {service:?})"));
-            // TODO: Implement start functionality
-            Ok(())
+            ui.separator();
+            manager.start(service.as_deref(), ui).await
+        }
+
+        Commands::Volume { command } => {
+            ui.separator();
+            match command {
+                VolumeCommands::Ls => manager.volume_ls(ui),
+                VolumeCommands::Inspect { name } => manager.volume_inspect(&name, ui),
+                VolumeCommands::Backup { name, archive } => {
+                    manager.volume_backup(&name, &archive, ui).await
+                }
+                VolumeCommands::Restore {
+                    name,
+                    archive,
+                    force,
+                } => {
+                    // Without a terminal to ask on, existing data still needs --force
+                    let answer = match manager.volume_has_data(&name) {
+                        Ok(true) if !force => confirm(
+                            ui,
+                            "overwrite",
+                            &[format!("the existing data of volume {name}")],
+                        ),
+                        _ => None,
+                    };
+                    if answer == Some(false) {
+                        ui.warning("Aborted; the volume was left as is");
+                        Ok(())
+                    } else {
+                        let force = force || answer == Some(true);
+                        manager.volume_restore(&name, &archive, force, ui).await
+                    }
+                }
+            }
+        }
+
+        Commands::Network { command } => {
+            ui.separator();
+            match command {
+                NetworkCommands::Ls => manager.network_ls(ui).await,
+                NetworkCommands::Inspect { name } => manager.network_inspect(&name, ui).await,
+            }
+        }
+
+        Commands::Events { format, filter } => {
+            if !scripted {
+                ui.separator();
+            }
+            manager.events(format, &filter, ui).await
+        }
+
+        Commands::Doctor | Commands::Config { .. } | Commands::Convert => {
+            unreachable!("handled before loading the config")
         }
     };
 
     if let Err(e) = result {
         ui.error(&format!("Command failed: {e}"));
+        // A crashed service's own exit code tells scripts more than a generic failure
+        let code = match e {
+            ComposeError::ServiceExited { exit_code, .. } => exit_code,
+            _ => 1,
+        };
+        std::process::exit(if code == 0 { 1 } else { code });
+    }
+
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+
+    Ok(())
+}
+
+// Ask on the terminal before destroying data, listing exactly what goes.
+// Nothing to list is a yes; `None` means stdin isn't a terminal to ask on.
+fn confirm(ui: &dyn Reporter, action: &str, targets: &[String]) -> Option<bool> {
+    if targets.is_empty() {
+        return Some(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+
+    ui.warning(&format!("This will {}:", action));
+    for target in targets {
+        ui.warning(&format!("  {target}"));
+    }
+    eprint!("Continue? [y/N] ");
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return Some(false);
+    }
+    Some(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Run every validation pass, reporting all problems together
+fn validate(config: &ContainerComposeConfig, args: &Cli) -> Result<(), Vec<ValidationError>> {
+    let mut errors = config.validate().err().unwrap_or_default();
+    errors.extend(
+        config
+            .validate_files(&args.project_directory())
+            .err()
+            .unwrap_or_default(),
+    );
+    errors.extend(
+        config
+            .validate_profiles(&args.profiles)
+            .err()
+            .unwrap_or_default(),
+    );
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// Validate the config and print either the resolved file or every validation error
+async fn run_config(
+    args: &Cli,
+    format: OutputFormat,
+    print_project_name: bool,
+    resolve_image_digests: bool,
+    ui: &dyn Reporter,
+) -> Result<()> {
+    let mut result = ContainerComposeConfig::from_file(&args.file)
+        .map_err(|e| {
+            vec![ValidationError {
+                field: "file".to_string(),
+                service: None,
+                message: format!("Failed to load configuration: {e}"),
+            }]
+        })
+        .and_then(|mut config| {
+            validate(&config, args)?;
+            config.apply_profiles(&args.profiles);
+            Ok(config)
+        });
+
+    if let Ok(config) = &result {
+        for warning in config.warnings() {
+            eprintln!("Warning: {warning}");
+        }
+    }
+
+    if let Some(name) = &args.project_name
+        && !is_valid_project_name(name)
+    {
+        ui.error(&format!("Invalid project name '{name}'"));
+        std::process::exit(1);
+    }
+
+    if print_project_name && let Ok(config) = &result {
+        println!(
+            "{}",
+            config.project_name(args.project_name.as_deref(), &args.project_directory())
+        );
+        return Ok(());
+    }
+
+    if resolve_image_digests && let Ok(config) = &mut result {
+        // Build-only services have no image to pin
+        for (name, service) in config
+            .services
+            .iter_mut()
+            .filter(|(_, s)| !s.image.is_empty())
+        {
+            match ContainerManager::resolve_image_digest(&args.container_binary, &service.image)
+                .await
+            {
+                Some(digest) => {
+                    service.image = format!("{}@{}", image_without_digest(&service.image), digest)
+                }
+                None => eprintln!(
+                    "Warning: could not resolve digest for service '{}' image '{}' (pull it first)",
+                    name, service.image
+                ),
+            }
+        }
+    }
+
+    match (result, format) {
+        (Ok(config), OutputFormat::Text) => print!("{}", serde_yaml::to_string(&config)?),
+        (Ok(config), OutputFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&config)?)
+        }
+        (Err(errors), OutputFormat::Text) => {
+            for error in &errors {
+                ui.error(&error.message);
+            }
+            std::process::exit(1);
+        }
+        (Err(errors), OutputFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&errors)?);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+// Validate the config and print it translated into a docker-compose file
+fn run_convert(args: &Cli, ui: &dyn Reporter) -> Result<()> {
+    let config = match ContainerComposeConfig::from_file(&args.file) {
+        Ok(config) => config,
+        Err(e) => {
+            ui.error(&format!("Failed to load configuration: {e}"));
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(errors) = validate(&config, args) {
+        for error in &errors {
+            ui.error(&error.message);
+        }
         std::process::exit(1);
     }
 
+    let (compose, mut warnings) = DockerCompose::from_config(&config);
+    warnings.splice(0..0, config.warnings());
+    for warning in &warnings {
+        eprintln!("Warning: {warning}");
+    }
+
+    print!("{}", serde_yaml::to_string(&compose)?);
     Ok(())
 }
@@ -1,139 +1,628 @@
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::io::IsTerminal;
+use std::process::Output;
+use std::sync::Mutex;
 use std::time::Duration;
+use tokio::process::Command as AsyncCommand;
 
-pub struct UI;
+// When to emit ANSI colors and animated spinners
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum AnsiMode {
+    /// Color only when writing to a terminal (and NO_COLOR is unset)
+    Auto,
+    /// Always emit colors, even when NO_COLOR is set
+    Always,
+    /// Never emit colors or spinners
+    Never,
+}
 
-impl UI {
-    pub fn new() -> Self {
-        Self
+// How commands that support machine-readable output print their results
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+// Everything `ContainerManager` reports while it works. `UI` renders it on the
+// terminal; embedders can implement it to capture or discard the output.
+// Progress bars default to hidden ones and command echoing to nothing.
+pub trait Reporter: Send + Sync {
+    fn header(&self, text: &str);
+    fn success(&self, text: &str);
+    fn info(&self, text: &str);
+    fn warning(&self, text: &str);
+    fn error(&self, text: &str);
+    fn table_header(&self, headers: &[&str]);
+    fn table_row(&self, cells: &[&str], status_color: Option<&str>);
+    fn log_line(&self, service: &str, width: usize, line: &str, color: bool);
+    fn log_raw(&self, line: &str);
+    // A line of command output meant for scripts, such as `ps -q` IDs
+    fn output(&self, line: &str);
+
+    fn separator(&self) {}
+
+    // Report a container lifecycle change; text reporters get the usual lines
+    fn service_event(&self, service: &str, action: ServiceAction) {
+        match action {
+            ServiceAction::Started { container_id } => {
+                self.inline_success(&format!("{} started ({})", service, container_id))
+            }
+            ServiceAction::Stopped => self.inline_success(&format!("{} stopped", service)),
+            ServiceAction::Restarted => self.inline_success(&format!("{} restarted", service)),
+            ServiceAction::NotFound => self.inline_info(&format!("{} not found", service)),
+            // Progress bars show the transitions and errors are reported by the caller
+            ServiceAction::Starting
+            | ServiceAction::Stopping
+            | ServiceAction::Restarting
+            | ServiceAction::Failed { .. } => {}
+        }
     }
 
-    // Print a styled header
-    pub fn header(&self, text: &str) {
-        println!("{}", text.bright_blue().bold());
+    fn inline_success(&self, text: &str) {
+        self.success(text);
     }
 
-    // Print a success message
-    pub fn success(&self, text: &str) {
-        println!("{} {}", "[✓]".green().bold(), text.green());
+    fn inline_info(&self, text: &str) {
+        self.info(text);
     }
 
-    // Print an info message
-    pub fn info(&self, text: &str) {
-        println!("{} {}", "[i]".blue().bold(), text);
+    fn inline_warning(&self, text: &str) {
+        self.warning(text);
     }
 
-    // Print a warning message
-    pub fn warning(&self, text: &str) {
-        println!("{} {}", "[!]".yellow().bold(), text.yellow());
+    fn command(&self, _cmd: &AsyncCommand) {}
+
+    fn command_output(&self, _output: &Output) {}
+
+    fn create_pull_progress(&self, _image: &str) -> ProgressBar {
+        ProgressBar::hidden()
     }
 
-    // Print an error message
-    pub fn error(&self, text: &str) {
-        println!("{} {}", "[✗]".red().bold(), text.red().bold());
+    fn set_pull_percent(&self, pb: &ProgressBar, percent: u64) {
+        pb.set_position(percent);
     }
 
-    // Create a progress bar for image pulling
-    pub fn create_pull_progress(&self, image: &str) -> ProgressBar {
-        let pb = ProgressBar::new_spinner();
+    fn set_pull_status(&self, _pb: &ProgressBar, _image: &str, _status: &str) {}
+
+    fn create_stop_progress(&self, _service: &str) -> ProgressBar {
+        ProgressBar::hidden()
+    }
+
+    fn create_start_progress(&self, _service: &str) -> ProgressBar {
+        ProgressBar::hidden()
+    }
+
+    fn create_restart_progress(&self, _service: &str) -> ProgressBar {
+        ProgressBar::hidden()
+    }
+}
+
+pub struct UI {
+    spinners: bool,
+    verbosity: u8,
+    // Shared by every spinner so concurrent ones render on separate lines
+    progress: MultiProgress,
+}
+
+impl UI {
+    // Every output path obeys the color and verbosity decisions made here
+    pub fn new(ansi: AnsiMode, verbosity: u8) -> Self {
+        let color = match ansi {
+            AnsiMode::Always => true,
+            AnsiMode::Never => false,
+            AnsiMode::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+        };
+        colored::control::set_override(color);
+
+        Self {
+            spinners: ansi != AnsiMode::Never,
+            verbosity,
+            progress: MultiProgress::new(),
+        }
+    }
+
+    // Print a line without tearing any spinners that are currently drawn
+    fn print(&self, line: impl std::fmt::Display) {
+        self.progress.suspend(|| println!("{}", line));
+    }
+
+    // Create an animated spinner, or a hidden one when spinners are disabled
+    fn spinner(&self, template: &str, msg: &str) -> ProgressBar {
+        if !self.spinners {
+            return ProgressBar::hidden();
+        }
+
+        let pb = self.progress.add(ProgressBar::new_spinner());
         pb.set_style(
             ProgressStyle::default_spinner()
                 .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
-                .template("{spinner:.cyan} Pulling {msg}...")
+                .template(template)
                 .unwrap(),
         );
-        pb.set_message(image.to_string());
+        pb.set_message(msg.to_string());
         pb.enable_steady_tick(Duration::from_millis(100));
         pb
     }
+}
+
+impl Reporter for UI {
+    // Print a styled header
+    fn header(&self, text: &str) {
+        self.print(text.bright_blue().bold());
+    }
+
+    // Print a success message
+    fn success(&self, text: &str) {
+        self.print(format!("{} {}", "[✓]".green().bold(), text.green()));
+    }
+
+    // Print an info message
+    fn info(&self, text: &str) {
+        self.print(format!("{} {}", "[i]".blue().bold(), text));
+    }
+
+    // Print a warning message
+    fn warning(&self, text: &str) {
+        self.print(format!("{} {}", "[!]".yellow().bold(), text.yellow()));
+    }
+
+    // Print an error message
+    fn error(&self, text: &str) {
+        self.print(format!("{} {}", "[✗]".red().bold(), text.red().bold()));
+    }
+
+    // Create a progress bar for image pulling
+    fn create_pull_progress(&self, image: &str) -> ProgressBar {
+        self.spinner("{spinner:.cyan} Pulling {msg}...", image)
+    }
+
+    // Turn a pull spinner into a percentage bar and advance it
+    fn set_pull_percent(&self, pb: &ProgressBar, percent: u64) {
+        if pb.length().is_none() {
+            pb.set_length(100);
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{msg} [{bar:30.cyan/blue}] {pos:>3}%")
+                    .unwrap()
+                    .progress_chars("=> "),
+            );
+        }
+        pb.set_position(percent);
+    }
+
+    // Show the latest status line of a pull that reports no percentage
+    fn set_pull_status(&self, pb: &ProgressBar, image: &str, status: &str) {
+        pb.set_message(format!("{} ({})", image, status));
+    }
 
     // Create a progress bar for stopping containers
-    pub fn create_stop_progress(&self, service: &str) -> ProgressBar {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
-                .template("{spinner:.yellow} Stopping {msg}...")
-                .unwrap(),
-        );
-        pb.set_message(service.to_string());
-        pb.enable_steady_tick(Duration::from_millis(100));
-        pb
+    fn create_stop_progress(&self, service: &str) -> ProgressBar {
+        self.spinner("{spinner:.yellow} Stopping {msg}...", service)
     }
 
     // Create a progress bar for starting containers
-    pub fn create_start_progress(&self, service: &str) -> ProgressBar {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
-                .template("{spinner:.green} Starting {msg}...")
-                .unwrap(),
-        );
-        pb.set_message(service.to_string());
-        pb.enable_steady_tick(Duration::from_millis(100));
-        pb
+    fn create_start_progress(&self, service: &str) -> ProgressBar {
+        self.spinner("{spinner:.green} Starting {msg}...", service)
+    }
+
+    // Create a progress bar for restarting containers
+    fn create_restart_progress(&self, service: &str) -> ProgressBar {
+        self.spinner("{spinner:.blue} Restarting {msg}...", service)
     }
 
     // Print a table header
-    pub fn table_header(&self, headers: &[&str]) {
+    fn table_header(&self, headers: &[&str]) {
         let header_line = headers
             .iter()
             .map(|h| format!("{:<15}", h.bold()))
             .collect::<Vec<_>>()
             .join(" ");
 
-        println!("{}", header_line);
-        println!("{}", "-".repeat(header_line.len()).dimmed());
+        self.print(&header_line);
+        self.print("-".repeat(header_line.len()).dimmed());
     }
 
     // Print a table row
-    pub fn table_row(&self, cells: &[&str], status_color: Option<&str>) {
+    fn table_row(&self, cells: &[&str], status_color: Option<&str>) {
         let row = cells
             .iter()
             .enumerate()
-            .map(|(i, cell)| {
-                if i == 1 && status_color.is_some() {
-                    // Status column
-                    match status_color.unwrap() {
-                        "green" => format!("{:<15}", cell.green()),
-                        "red" => format!("{:<15}", cell.red()),
-                        "yellow" => format!("{:<15}", cell.yellow()),
-                        _ => format!("{:<15}", cell),
-                    }
-                } else {
-                    format!("{:<15}", cell)
-                }
+            .map(|(i, cell)| match status_color {
+                // Status column
+                Some(color) if i == 1 => match color {
+                    "green" => format!("{:<15}", cell.green()),
+                    "red" => format!("{:<15}", cell.red()),
+                    "yellow" => format!("{:<15}", cell.yellow()),
+                    _ => format!("{:<15}", cell),
+                },
+                _ => format!("{:<15}", cell),
             })
             .collect::<Vec<_>>()
             .join(" ");
 
-        println!("{}", row);
+        self.print(row);
     }
 
-    // Print command being executed (for verbose mode)
-    pub fn command(&self, cmd: &str) {
-        println!("{} {}", "[>]".cyan().bold(), cmd.dimmed());
+    // Print the exact argv of a command about to run (from -v)
+    fn command(&self, cmd: &AsyncCommand) {
+        if self.verbosity >= 1 {
+            self.print(format!(
+                "{} {}",
+                "[>]".cyan().bold(),
+                render_argv(cmd).dimmed()
+            ));
+        }
+    }
+
+    // Print the captured stdout and stderr of a finished command (from -vv)
+    fn command_output(&self, output: &Output) {
+        if self.verbosity < 2 {
+            return;
+        }
+        for (stream, bytes) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+            for line in String::from_utf8_lossy(bytes).lines() {
+                self.print(format!(
+                    "{} {}",
+                    format!("[{}]", stream).dimmed(),
+                    line.dimmed()
+                ));
+            }
+        }
     }
 
     // Print a separator line
-    pub fn separator(&self) {
-        println!("{}", "=".repeat(60).dimmed());
+    fn separator(&self) {
+        self.print("=".repeat(60).dimmed());
     }
 
     // Print inline success message
-    pub fn inline_success(&self, text: &str) {
-        println!("{} {}", "[✓]".green().bold(), text.green());
+    fn inline_success(&self, text: &str) {
+        self.print(format!("{} {}", "[✓]".green().bold(), text.green()));
     }
 
     // Print inline info message
-    pub fn inline_info(&self, text: &str) {
-        println!("{} {}", "[i]".blue().bold(), text);
+    fn inline_info(&self, text: &str) {
+        self.print(format!("{} {}", "[i]".blue().bold(), text));
     }
 
     // Print inline warning message
-    pub fn inline_warning(&self, text: &str) {
-        println!("{} {}", "[!]".yellow().bold(), text.yellow());
+    fn inline_warning(&self, text: &str) {
+        self.print(format!("{} {}", "[!]".yellow().bold(), text.yellow()));
+    }
+
+    // Print a line of container output behind an aligned `service |` gutter
+    fn log_line(&self, service: &str, width: usize, line: &str, color: bool) {
+        let prefix = format!("{:<width$} |", service, width = width);
+        if color {
+            self.print(format!("{} {}", prefix.color(service_color(service)), line));
+        } else {
+            self.print(format!("{} {}", prefix, line));
+        }
+    }
+
+    // Print a line of container output without any prefix
+    fn log_raw(&self, line: &str) {
+        self.print(line);
+    }
+
+    fn output(&self, line: &str) {
+        self.print(line);
+    }
+}
+
+// A reporter that discards everything, for callers that only want results
+pub struct NullReporter;
+
+impl Reporter for NullReporter {
+    fn header(&self, _text: &str) {}
+    fn success(&self, _text: &str) {}
+    fn info(&self, _text: &str) {}
+    fn warning(&self, _text: &str) {}
+    fn error(&self, _text: &str) {}
+    fn table_header(&self, _headers: &[&str]) {}
+    fn table_row(&self, _cells: &[&str], _status_color: Option<&str>) {}
+    fn log_line(&self, _service: &str, _width: usize, _line: &str, _color: bool) {}
+    fn log_raw(&self, _line: &str) {}
+    fn output(&self, _line: &str) {}
+}
+
+// What happened to one service container. `UI` renders these as text; the
+// JSON reporter emits them as `{"type":"service","service":..,"action":..}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ServiceAction {
+    Starting,
+    Started { container_id: String },
+    Stopping,
+    Stopped,
+    Restarting,
+    Restarted,
+    // The container was already gone when asked to stop
+    NotFound,
+    Failed { error: String },
+}
+
+// One thing a reporter was handed, without any styling. This is also the schema
+// of `--json` output: each message is one JSON object per line, tagged by `type`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Header {
+        text: String,
+    },
+    Success {
+        text: String,
+    },
+    Info {
+        text: String,
+    },
+    Warning {
+        text: String,
+    },
+    Error {
+        text: String,
+    },
+    TableHeader {
+        headers: Vec<String>,
+    },
+    TableRow {
+        cells: Vec<String>,
+    },
+    // A line of container output; `service` is None when printed without a prefix
+    Log {
+        service: Option<String>,
+        line: String,
+    },
+    // A line of command output meant for scripts rather than people
+    Output {
+        line: String,
+    },
+    // The shell-quoted argv of a command about to run
+    Command {
+        argv: String,
+    },
+    Service {
+        service: String,
+        #[serde(flatten)]
+        action: ServiceAction,
+    },
+    // An image pull that just began
+    Pull {
+        image: String,
+    },
+}
+
+impl Message {
+    fn text(&self) -> Option<&String> {
+        match self {
+            Message::Header { text }
+            | Message::Success { text }
+            | Message::Info { text }
+            | Message::Warning { text }
+            | Message::Error { text } => Some(text),
+            _ => None,
+        }
+    }
+}
+
+// Write the `Message` for every `Reporter` call to `emit`, so recording and
+// JSON reporters share one mapping
+macro_rules! impl_message_reporter {
+    ($reporter:ty) => {
+        impl Reporter for $reporter {
+            fn header(&self, text: &str) {
+                self.emit(Message::Header {
+                    text: text.to_string(),
+                });
+            }
+
+            fn success(&self, text: &str) {
+                self.emit(Message::Success {
+                    text: text.to_string(),
+                });
+            }
+
+            fn info(&self, text: &str) {
+                self.emit(Message::Info {
+                    text: text.to_string(),
+                });
+            }
+
+            fn warning(&self, text: &str) {
+                self.emit(Message::Warning {
+                    text: text.to_string(),
+                });
+            }
+
+            fn error(&self, text: &str) {
+                self.emit(Message::Error {
+                    text: text.to_string(),
+                });
+            }
+
+            fn table_header(&self, headers: &[&str]) {
+                self.emit(Message::TableHeader {
+                    headers: headers.iter().map(|h| h.to_string()).collect(),
+                });
+            }
+
+            fn table_row(&self, cells: &[&str], _status_color: Option<&str>) {
+                self.emit(Message::TableRow {
+                    cells: cells.iter().map(|c| c.to_string()).collect(),
+                });
+            }
+
+            fn log_line(&self, service: &str, _width: usize, line: &str, _color: bool) {
+                self.emit(Message::Log {
+                    service: Some(service.to_string()),
+                    line: line.to_string(),
+                });
+            }
+
+            fn log_raw(&self, line: &str) {
+                self.emit(Message::Log {
+                    service: None,
+                    line: line.to_string(),
+                });
+            }
+
+            fn output(&self, line: &str) {
+                self.emit(Message::Output {
+                    line: line.to_string(),
+                });
+            }
+
+            fn command(&self, cmd: &AsyncCommand) {
+                self.emit(Message::Command {
+                    argv: render_argv(cmd),
+                });
+            }
+
+            fn service_event(&self, service: &str, action: ServiceAction) {
+                self.emit(Message::Service {
+                    service: service.to_string(),
+                    action,
+                });
+            }
+
+            fn create_pull_progress(&self, image: &str) -> ProgressBar {
+                self.emit(Message::Pull {
+                    image: image.to_string(),
+                });
+                ProgressBar::hidden()
+            }
+        }
+    };
+}
+
+// A reporter that records every message in order, so tests can assert on what
+// a `ContainerManager` call reported instead of capturing stdout
+#[derive(Default)]
+pub struct CollectingReporter {
+    messages: Mutex<Vec<Message>>,
+}
+
+impl CollectingReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Everything reported so far, oldest first
+    pub fn messages(&self) -> Vec<Message> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    // The text of every warning reported so far
+    pub fn warnings(&self) -> Vec<String> {
+        self.texts(|message| matches!(message, Message::Warning { .. }))
+    }
+
+    // The text of every error reported so far
+    pub fn errors(&self) -> Vec<String> {
+        self.texts(|message| matches!(message, Message::Error { .. }))
+    }
+
+    // The argv of every command run so far
+    pub fn commands(&self) -> Vec<String> {
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|message| match message {
+                Message::Command { argv } => Some(argv.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn texts(&self, keep: impl Fn(&Message) -> bool) -> Vec<String> {
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|message| keep(message))
+            .filter_map(Message::text)
+            .cloned()
+            .collect()
+    }
+
+    fn emit(&self, message: Message) {
+        self.messages.lock().unwrap().push(message);
+    }
+}
+
+impl_message_reporter!(CollectingReporter);
+
+// A reporter for `--json`: every message is printed to stdout as it happens,
+// one JSON object per line. Commands run are only included with -v, as in `UI`.
+pub struct JsonReporter {
+    verbose: bool,
+}
+
+impl JsonReporter {
+    pub fn new(verbosity: u8) -> Self {
+        Self {
+            verbose: verbosity >= 1,
+        }
+    }
+
+    fn emit(&self, message: Message) {
+        if matches!(message, Message::Command { .. }) && !self.verbose {
+            return;
+        }
+        if let Ok(line) = serde_json::to_string(&message) {
+            println!("{}", line);
+        }
+    }
+}
+
+impl_message_reporter!(JsonReporter);
+
+// Palette used for log prefixes, picked per service
+const LOG_COLORS: &[Color] = &[
+    Color::Cyan,
+    Color::Yellow,
+    Color::Green,
+    Color::Magenta,
+    Color::Blue,
+    Color::BrightCyan,
+    Color::BrightYellow,
+    Color::BrightGreen,
+    Color::BrightMagenta,
+    Color::BrightBlue,
+];
+
+// Derive a stable color from the service name (FNV-1a, so it never changes between runs)
+fn service_color(service: &str) -> Color {
+    let hash = service.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    LOG_COLORS[(hash % LOG_COLORS.len() as u64) as usize]
+}
+
+// Render a command as a shell-ready line, quoting arguments that need it
+fn render_argv(cmd: &AsyncCommand) -> String {
+    let cmd = cmd.as_std();
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Single-quote an argument unless it is made only of shell-safe characters
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
     }
 }
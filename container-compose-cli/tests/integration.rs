@@ -0,0 +1,1756 @@
+// Drive `ContainerManager` against tests/fixtures/fake-container, which records
+// every `container` invocation instead of touching a real runtime
+
+use container_compose::container::{
+    BuildOptions, ContainerManager, LogOptions, PsOptions, RecreatePolicy, RunOptions, StopOptions,
+    UpOptions,
+};
+use container_compose::convert::DockerCompose;
+use container_compose::ui::{CollectingReporter, Message, ServiceAction};
+use container_compose::{ComposeError, ContainerComposeConfig, NullReporter};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+// A scratch project directory wired to its own fake `container` binary
+struct FakeContainer {
+    dir: PathBuf,
+}
+
+impl FakeContainer {
+    fn new(test: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("container-compose-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("canned")).unwrap();
+
+        // Each test gets a wrapper pointing the fixture at its own directory,
+        // so tests running in parallel never share state
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fake-container");
+        let wrapper = dir.join("container");
+        fs::write(
+            &wrapper,
+            format!(
+                "#!/bin/sh\nFAKE_CONTAINER_DIR='{}' exec '{}' \"$@\"\n",
+                dir.display(),
+                fixture.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&wrapper, fs::Permissions::from_mode(0o755)).unwrap();
+
+        Self { dir }
+    }
+
+    // Load a compose file written into the project directory
+    fn manager(&self, compose: &str) -> ContainerManager {
+        let file = self.dir.join("container-compose.yml");
+        fs::write(&file, compose).unwrap();
+        let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+        ContainerManager::new(
+            config,
+            "proj".to_string(),
+            self.dir.clone(),
+            Some(self.dir.join("volumes")),
+            self.dir.join("container"),
+        )
+    }
+
+    // Answer an exact argv with fixed stdout instead of the simulated state
+    fn canned(&self, argv: &str, stdout: &str) {
+        fs::write(self.dir.join("canned").join(argv), stdout).unwrap();
+    }
+
+    // Make a running container stop on its own, as if its process exited
+    fn exit(&self, container: &str) {
+        let state = self.dir.join("state").join(container);
+        let line = fs::read_to_string(&state).unwrap();
+        fs::write(&state, line.replacen("running", "stopped", 1)).unwrap();
+    }
+
+    // Every argv the fake was called with, in order
+    fn calls(&self) -> Vec<String> {
+        fs::read_to_string(self.dir.join("argv.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    // The `<verb> <container>` calls, e.g. every container `stop`ped
+    fn targets_of(&self, verb: &str) -> Vec<String> {
+        self.calls()
+            .iter()
+            .filter_map(|call| call.strip_prefix(&format!("{} ", verb)))
+            .map(str::to_string)
+            .collect()
+    }
+
+    // The container names passed to `container run`, in order
+    fn started(&self) -> Vec<String> {
+        self.calls()
+            .iter()
+            .filter_map(|call| {
+                let args: Vec<&str> = call.split(' ').collect();
+                (args.first() == Some(&"run")).then(|| {
+                    let name = args.iter().position(|arg| *arg == "--name").unwrap();
+                    args[name + 1].to_string()
+                })
+            })
+            .collect()
+    }
+}
+
+impl Drop for FakeContainer {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+// Declared in the opposite order of their dependencies
+const CHAIN: &str = "
+services:
+  web:
+    image: nginx
+    depends_on: [api]
+  api:
+    image: node
+    depends_on: [db]
+  db:
+    image: postgres
+";
+
+#[tokio::test]
+async fn up_starts_dependencies_first() {
+    let fake = FakeContainer::new("up-order");
+    let mut manager = fake.manager(CHAIN);
+
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert_eq!(fake.started(), ["proj-db", "proj-api", "proj-web"]);
+}
+
+#[tokio::test]
+async fn up_orders_a_generated_stack_of_hundreds_of_services() {
+    let fake = FakeContainer::new("up-large");
+    // Each service depends on the next two, so the last one declared starts first
+    let mut compose = String::from("services:\n");
+    for i in 0..200 {
+        compose.push_str(&format!("  s{i}:\n    image: busybox\n    depends_on: ["));
+        let deps: Vec<String> = (i + 1..200).take(2).map(|d| format!("s{d}")).collect();
+        compose.push_str(&deps.join(", "));
+        compose.push_str("]\n");
+    }
+    let mut manager = fake.manager(&compose);
+
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    let expected: Vec<String> = (0..200).rev().map(|i| format!("proj-s{i}")).collect();
+    assert_eq!(fake.started(), expected);
+}
+
+#[tokio::test]
+async fn attach_leaves_services_running_when_logs_end() {
+    let fake = FakeContainer::new("attach-detach");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    let code = manager
+        .attach(false, None, &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert_eq!(code, None);
+    assert!(fake.targets_of("stop").is_empty());
+    assert!(fake.targets_of("rm").is_empty());
+}
+
+#[tokio::test]
+async fn exit_code_from_waits_for_its_own_service() {
+    let fake = FakeContainer::new("exit-code-from");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    fake.canned("inspect proj-api", r#"[{"status":"stopped","exitCode":1}]"#);
+    fake.canned("inspect proj-web", r#"[{"status":"stopped","exitCode":3}]"#);
+
+    // Another service exits first; only the reported one ends the run
+    let exits = async {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        fake.exit("proj-api");
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        fake.exit("proj-web");
+    };
+    let (code, ()) = tokio::join!(
+        manager.attach(true, Some("web"), &NullReporter, false),
+        exits
+    );
+
+    assert_eq!(code.unwrap(), Some(3));
+}
+
+#[tokio::test]
+async fn down_stops_dependents_first() {
+    let fake = FakeContainer::new("down-order");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    let reporter = CollectingReporter::new();
+    manager
+        .down(&StopOptions::default(), &reporter, false)
+        .await
+        .unwrap();
+
+    assert_eq!(fake.targets_of("stop"), ["proj-web", "proj-api", "proj-db"]);
+    assert_eq!(fake.targets_of("rm"), ["proj-web", "proj-api", "proj-db"]);
+    assert!(reporter.errors().is_empty());
+}
+
+#[tokio::test]
+async fn ps_reads_state_from_container_list() {
+    let fake = FakeContainer::new("ps");
+    let manager = fake.manager(CHAIN);
+    let labels = |service: &str| {
+        format!(
+            r#"{{"container-compose.project":"proj","container-compose.service":"{}"}}"#,
+            service
+        )
+    };
+    fs::create_dir_all(fake.dir.join("state")).unwrap();
+    fs::write(
+        fake.dir.join("state/proj-db"),
+        format!("running postgres:16 {}", labels("db")),
+    )
+    .unwrap();
+    fs::write(
+        fake.dir.join("state/proj-api"),
+        format!("stopped node:22 {}", labels("api")),
+    )
+    .unwrap();
+    fs::write(fake.dir.join("state/unrelated-box"), "running alpine {}").unwrap();
+
+    let reporter = CollectingReporter::new();
+    manager.ps(&PsOptions::default(), &reporter).await.unwrap();
+
+    let rows: Vec<Vec<String>> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::TableRow { cells } => Some(cells),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ["web", "Not Created", "N/A", "nginx"],
+            ["api", "Stopped", "proj-api", "node:22"],
+            ["db", "Running", "proj-db", "postgres:16"],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn up_refuses_to_replace_a_same_named_container_of_another_project() {
+    let fake = FakeContainer::new("name-conflict");
+    let mut manager = fake.manager(CHAIN);
+    fs::create_dir_all(fake.dir.join("state")).unwrap();
+    fs::write(
+        fake.dir.join("state/proj-web"),
+        r#"running nginx {"container-compose.project":"other","container-compose.service":"web"}"#,
+    )
+    .unwrap();
+
+    let options = UpOptions {
+        recreate: RecreatePolicy::Always,
+        ..UpOptions::default()
+    };
+    let err = manager
+        .up(&options, &NullReporter, false)
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(&err, ComposeError::NameConflict { container, .. } if container == "proj-web"),
+        "unexpected error: {}",
+        err
+    );
+    assert!(!fake.targets_of("stop").contains(&"proj-web".to_string()));
+    assert!(fake.targets_of("rm").is_empty());
+}
+
+#[tokio::test]
+async fn up_aliases_containers_by_service_name_on_each_network() {
+    let fake = FakeContainer::new("service-alias");
+    let mut manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+  api:
+    image: node
+    networks:
+      backend:
+        aliases: [apiv1]
+networks:
+  backend: {}
+",
+    );
+
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    let runs: Vec<String> = fake
+        .calls()
+        .into_iter()
+        .filter(|call| call.starts_with("run "))
+        .collect();
+    assert!(runs[0].contains("--network default --network-alias db "));
+    assert!(runs[1].contains("--network backend --network-alias api --network-alias apiv1 "));
+}
+
+#[tokio::test]
+async fn up_recreates_when_an_interpolated_value_changes() {
+    let fake = FakeContainer::new("interpolated-hash");
+    let yaml = "
+services:
+  web:
+    image: nginx
+    environment:
+      - GREETING=${CONTAINER_COMPOSE_TEST_GREETING}
+";
+    // Only this test reads the variable
+    unsafe { std::env::set_var("CONTAINER_COMPOSE_TEST_GREETING", "hello") };
+    for _ in 0..2 {
+        fake.manager(yaml)
+            .up(&UpOptions::default(), &NullReporter, false)
+            .await
+            .unwrap();
+    }
+    assert_eq!(fake.started(), ["proj-web"]);
+
+    unsafe { std::env::set_var("CONTAINER_COMPOSE_TEST_GREETING", "bye") };
+    fake.manager(yaml)
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert_eq!(fake.started(), ["proj-web", "proj-web"]);
+    assert!(fake.calls().last().unwrap().contains("GREETING=bye"));
+}
+
+#[test]
+fn network_options_configs_and_healthchecks_are_part_of_the_config_hash() {
+    let fake = FakeContainer::new("network-hash");
+    let file = fake.dir.join("container-compose.yml");
+    let hash = |service: &str| {
+        fs::write(
+            &file,
+            format!(
+                "
+services:
+  web:
+    image: nginx
+{}
+networks:
+  backend: {{}}
+configs:
+  site:
+    file: ./site.conf
+",
+                service
+            ),
+        )
+        .unwrap();
+        ContainerComposeConfig::from_file(&file.to_string_lossy())
+            .unwrap()
+            .services["web"]
+            .config_hash()
+    };
+
+    let plain = hash("    networks: [backend]");
+    let aliased = hash("    networks:\n      backend:\n        aliases: [www]");
+    assert_ne!(plain, aliased);
+    assert_ne!(
+        aliased,
+        hash("    networks:\n      backend:\n        aliases: [web1]")
+    );
+    assert_ne!(plain, hash("    networks: [backend]\n    configs: [site]"));
+    assert_ne!(
+        plain,
+        hash("    networks: [backend]\n    healthcheck:\n      test: [CMD, curl, -f, localhost]")
+    );
+    assert_eq!(plain, hash("    networks: [backend]"));
+}
+
+#[test]
+fn container_name_is_part_of_the_config_hash() {
+    let fake = FakeContainer::new("container-name-hash");
+    let file = fake.dir.join("container-compose.yml");
+    fs::write(
+        &file,
+        "
+services:
+  web:
+    image: nginx
+  named:
+    image: nginx
+    container_name: frontend
+",
+    )
+    .unwrap();
+
+    let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+
+    assert_ne!(
+        config.services["web"].config_hash(),
+        config.services["named"].config_hash()
+    );
+}
+
+#[tokio::test]
+async fn up_builds_volume_specs() {
+    let fake = FakeContainer::new("volumes");
+    fs::create_dir_all(fake.dir.join("conf")).unwrap();
+    let mut manager = fake.manager(
+        "
+services:
+  app:
+    image: busybox
+    volumes:
+      - ./conf:/etc/app:ro
+      - cache:/var/cache
+volumes:
+  cache: {}
+",
+    );
+
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    let run = fake
+        .calls()
+        .into_iter()
+        .find(|call| call.starts_with("run "))
+        .unwrap();
+    let conf = fake.dir.join("conf").canonicalize().unwrap();
+    assert!(
+        run.contains(&format!("--volume {}:/etc/app:ro", conf.display())),
+        "{}",
+        run
+    );
+    assert!(
+        run.contains(&format!(
+            "--volume {}:/var/cache",
+            fake.dir.join("volumes/cache").display()
+        )),
+        "{}",
+        run
+    );
+    assert!(fake.dir.join("volumes/cache").is_dir());
+}
+
+#[tokio::test]
+async fn up_reports_service_events() {
+    let fake = FakeContainer::new("events");
+    let mut manager = fake.manager(CHAIN);
+    let reporter = CollectingReporter::new();
+
+    manager
+        .up(&UpOptions::default(), &reporter, false)
+        .await
+        .unwrap();
+
+    let started: Vec<String> = reporter
+        .messages()
+        .into_iter()
+        .filter(|message| {
+            matches!(
+                message,
+                Message::Service {
+                    action: ServiceAction::Started { .. },
+                    ..
+                }
+            )
+        })
+        .map(|message| serde_json::to_string(&message).unwrap())
+        .collect();
+    assert_eq!(
+        started[0],
+        r#"{"type":"service","service":"db","action":"started","container_id":"proj-db"}"#
+    );
+    assert_eq!(started.len(), 3);
+}
+
+#[tokio::test]
+async fn lifecycle_transitions_are_reported_before_their_outcome() {
+    let fake = FakeContainer::new("transitions");
+    let mut manager = fake.manager(
+        "
+services:
+  web:
+    image: nginx
+",
+    );
+    let reporter = CollectingReporter::new();
+
+    manager
+        .up(&UpOptions::default(), &reporter, false)
+        .await
+        .unwrap();
+    manager
+        .restart(Some("web".to_string()), false, &reporter)
+        .await
+        .unwrap();
+    manager
+        .stop(None, &StopOptions::default(), &reporter, false)
+        .await
+        .unwrap();
+
+    let actions: Vec<String> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::Service { action, .. } => {
+                Some(serde_json::to_value(action).unwrap()["action"].to_string())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            r#""starting""#,
+            r#""started""#,
+            r#""restarting""#,
+            r#""restarted""#,
+            r#""stopping""#,
+            r#""stopped""#
+        ]
+    );
+}
+
+#[tokio::test]
+async fn up_reports_every_bad_mount_before_starting() {
+    let fake = FakeContainer::new("bad-mounts");
+    let mut manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+    volumes: [./missing:/data]
+  web:
+    image: nginx
+    volumes: [./gone:/srv]
+",
+    );
+
+    let err = manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap_err();
+
+    let ComposeError::InvalidMounts(problems) = err else {
+        panic!("expected InvalidMounts, got {}", err);
+    };
+    let services: Vec<&str> = problems.iter().map(|(s, _)| s.as_str()).collect();
+    assert_eq!(services, ["db", "web"]);
+    assert!(fake.started().is_empty());
+}
+
+#[tokio::test]
+async fn build_passes_flags_and_interpolated_args() {
+    let fake = FakeContainer::new("build");
+    fs::create_dir_all(fake.dir.join("app")).unwrap();
+    let manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+  app:
+    image: example/app:dev
+    build:
+      context: ./app
+      dockerfile: Dockerfile.dev
+      args:
+        USER_HOME: ${HOME}
+        CHANNEL: ${BUILD_CHANNEL_THAT_IS_UNSET:-stable}
+",
+    );
+
+    let options = BuildOptions {
+        no_cache: true,
+        pull: true,
+    };
+    manager.build(None, &options, &NullReporter).await.unwrap();
+
+    let app = fake.dir.join("app");
+    assert_eq!(
+        fake.targets_of("build"),
+        [format!(
+            "--tag example/app:dev --file {}/Dockerfile.dev --no-cache --pull --build-arg USER_HOME={} --build-arg CHANNEL=stable {}",
+            app.display(),
+            std::env::var("HOME").unwrap_or_default(),
+            app.display()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn build_only_services_get_a_project_image_tag() {
+    let fake = FakeContainer::new("build-only");
+    fs::create_dir_all(fake.dir.join("app")).unwrap();
+    let mut manager = fake.manager(
+        "
+services:
+  app:
+    build: ./app
+",
+    );
+    let file = fake.dir.join("container-compose.yml");
+    let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+    assert!(config.validate().is_ok());
+
+    manager
+        .pull(None, false, false, &NullReporter)
+        .await
+        .unwrap();
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert!(fake.targets_of("images pull").is_empty());
+    assert_eq!(
+        fake.targets_of("build"),
+        [format!("--tag proj-app {}", fake.dir.join("app").display())]
+    );
+    assert!(fake.calls().last().unwrap().ends_with(" proj-app"));
+}
+
+#[tokio::test]
+async fn ps_flags_containers_restarted_too_often() {
+    let fake = FakeContainer::new("ps-restarts");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    fake.canned(
+        "inspect proj-api",
+        r#"[{"status":"running","restartCount":7}]"#,
+    );
+    fake.canned(
+        "inspect proj-db",
+        r#"[{"status":"running","restartCount":2}]"#,
+    );
+
+    let reporter = CollectingReporter::new();
+    let options = PsOptions {
+        restarts: Some(3),
+        ..PsOptions::default()
+    };
+    manager.ps(&options, &reporter).await.unwrap();
+
+    let restarts: Vec<String> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::TableRow { cells } => Some(cells[4].clone()),
+            _ => None,
+        })
+        .collect();
+    // web's inspect data has no restart count
+    assert_eq!(restarts, ["0", "7 (>3)", "2"]);
+}
+
+#[tokio::test]
+async fn update_recreates_changed_images_and_restarts_dependents() {
+    let fake = FakeContainer::new("update");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    // postgres resolves to a new digest once pulled; the other images never change
+    fake.canned("images inspect postgres", r#"[{"digest":"sha256:aaaa"}]"#);
+    fs::write(
+        fake.dir.join("canned/images inspect postgres.next"),
+        r#"[{"digest":"sha256:bbbb"}]"#,
+    )
+    .unwrap();
+
+    manager
+        .update(None, true, &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert_eq!(fake.targets_of("rm"), ["proj-db"]);
+    assert_eq!(
+        fake.started(),
+        ["proj-db", "proj-api", "proj-web", "proj-db"]
+    );
+    assert_eq!(fake.targets_of("start"), ["proj-api", "proj-web"]);
+}
+
+#[tokio::test]
+async fn run_layers_overrides_and_leaves_ports_alone() {
+    let fake = FakeContainer::new("run");
+    let manager = fake.manager(
+        "
+services:
+  app:
+    image: node
+    ports: [\"3000:3000\"]
+    working_dir: /srv
+    environment:
+      NODE_ENV: production
+      PORT: \"3000\"
+",
+    );
+
+    let options = RunOptions {
+        workdir: Some("/tmp".to_string()),
+        user: Some("1000".to_string()),
+        env: vec!["NODE_ENV=test".to_string()],
+        no_tty: true,
+        ..RunOptions::default()
+    };
+    let command = ["npm".to_string(), "test".to_string()];
+    manager
+        .run("app", &command, &options, &NullReporter)
+        .await
+        .unwrap();
+
+    let run = format!("run --name proj-app-run-{}", std::process::id());
+    let calls = fake.calls();
+    let args = calls
+        .iter()
+        .find_map(|call| call.strip_prefix(&run))
+        .unwrap();
+    assert_eq!(
+        args,
+        " --interactive --label container-compose.project=proj --label container-compose.service=app --label container-compose.oneoff=true --user 1000 --env PORT=3000 --env NODE_ENV=test --workdir /tmp node npm test"
+    );
+
+    // The one-off container is neither an instance of the service nor an orphan
+    let reporter = CollectingReporter::new();
+    manager.ps(&PsOptions::default(), &reporter).await.unwrap();
+    assert!(
+        reporter.messages().contains(&Message::TableRow {
+            cells: ["app", "Not Created", "N/A", "node"]
+                .map(str::to_string)
+                .to_vec()
+        })
+    );
+    let orphans = PsOptions {
+        orphans: true,
+        ..PsOptions::default()
+    };
+    let reporter = CollectingReporter::new();
+    manager.ps(&orphans, &reporter).await.unwrap();
+    assert!(
+        !reporter
+            .messages()
+            .iter()
+            .any(|message| matches!(message, Message::TableRow { .. }))
+    );
+}
+
+#[tokio::test]
+async fn up_keep_going_skips_dependents_of_failed_services() {
+    let fake = FakeContainer::new("keep-going");
+    let mut manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+  api:
+    image: node
+    depends_on: [db]
+    configs: [missing]
+  web:
+    image: nginx
+    depends_on: [api]
+  worker:
+    image: node
+    depends_on: [db]
+",
+    );
+    let options = UpOptions {
+        keep_going: true,
+        ..UpOptions::default()
+    };
+    let err = manager
+        .up(&options, &NullReporter, false)
+        .await
+        .unwrap_err();
+
+    let ComposeError::ServicesFailed { failures, .. } = err else {
+        panic!("expected ServicesFailed, got {}", err);
+    };
+    assert!(matches!(failures[0], (ref s, ComposeError::ConfigNotFound(_)) if s == "api"));
+    assert!(
+        matches!(failures[1], (ref s, ComposeError::DependencyFailed(ref d)) if s == "web" && d == "api")
+    );
+    assert_eq!(failures.len(), 2);
+    assert_eq!(fake.started(), ["proj-db", "proj-worker"]);
+}
+
+#[test]
+fn profiles_flag_dependencies_they_leave_out() {
+    let fake = FakeContainer::new("profiles");
+    let file = fake.dir.join("container-compose.yml");
+    fs::write(
+        &file,
+        "
+services:
+  web:
+    image: nginx
+    depends_on: [db]
+  db:
+    image: postgres
+    profiles: [backend]
+  debug:
+    image: busybox
+    profiles: [tools]
+",
+    )
+    .unwrap();
+    let mut config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+
+    let errors = config.validate_profiles(&[]).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].service.as_deref(), Some("web"));
+    assert!(errors[0].message.contains("--profile backend"));
+
+    let active = ["backend".to_string()];
+    assert!(config.validate_profiles(&active).is_ok());
+    config.apply_profiles(&active);
+    let services: Vec<&String> = config.services.keys().collect();
+    assert_eq!(services, ["web", "db"]);
+}
+
+#[tokio::test]
+async fn containers_of_services_in_inactive_profiles_are_not_orphans() {
+    let fake = FakeContainer::new("profile-orphans");
+    let file = fake.dir.join("container-compose.yml");
+    fs::write(
+        &file,
+        "
+services:
+  web:
+    image: nginx
+  debug:
+    image: busybox
+    profiles: [tools]
+",
+    )
+    .unwrap();
+    let mut config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+    config.apply_profiles(&[]);
+    assert_eq!(config.disabled_services, ["debug"]);
+    let manager = ContainerManager::new(
+        config,
+        "proj".to_string(),
+        fake.dir.clone(),
+        Some(fake.dir.join("volumes")),
+        fake.dir.join("container"),
+    );
+    fs::create_dir_all(fake.dir.join("state")).unwrap();
+    fs::write(
+        fake.dir.join("state/proj-debug"),
+        r#"running busybox {"container-compose.project":"proj","container-compose.service":"debug"}"#,
+    )
+    .unwrap();
+
+    let options = StopOptions {
+        remove_orphans: true,
+        ..StopOptions::default()
+    };
+    manager
+        .stop(None, &options, &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert!(fake.targets_of("stop").is_empty());
+    assert!(fake.targets_of("rm").is_empty());
+}
+
+#[test]
+fn volumes_with_data_lists_what_down_would_delete() {
+    let fake = FakeContainer::new("doomed-volumes");
+    let manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+    volumes: [data:/var/lib/postgresql/data, cache:/cache]
+volumes:
+  data: {}
+  cache: {}
+",
+    );
+    fs::create_dir_all(fake.dir.join("volumes/data")).unwrap();
+    fs::write(fake.dir.join("volumes/data/PG_VERSION"), "16").unwrap();
+    fs::create_dir_all(fake.dir.join("volumes/cache")).unwrap();
+
+    let doomed = manager.volumes_with_data().unwrap();
+    assert_eq!(
+        doomed,
+        [("data".to_string(), fake.dir.join("volumes/data"))]
+    );
+    assert!(manager.volume_has_data("data").unwrap());
+    assert!(!manager.volume_has_data("cache").unwrap());
+}
+
+#[test]
+fn remove_volumes_only_deletes_declared_volume_data() {
+    let fake = FakeContainer::new("remove-volumes");
+    let manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+    volumes: [data:/var/lib/postgresql/data]
+volumes:
+  data: {}
+  cache: {}
+",
+    );
+    fs::create_dir_all(fake.dir.join("volumes/data")).unwrap();
+    fs::write(fake.dir.join("volumes/data/PG_VERSION"), "16").unwrap();
+    fs::create_dir_all(fake.dir.join("volumes/cache")).unwrap();
+    // Another project's volume in the same base directory
+    fs::create_dir_all(fake.dir.join("volumes/elsewhere")).unwrap();
+
+    manager.remove_volumes(&NullReporter).unwrap();
+
+    assert!(!fake.dir.join("volumes/data").exists());
+    assert!(!fake.dir.join("volumes/cache").exists());
+    assert!(fake.dir.join("volumes/elsewhere").exists());
+}
+
+#[tokio::test]
+async fn volume_restore_keeps_data_when_the_archive_is_corrupt() {
+    let fake = FakeContainer::new("restore-corrupt");
+    let manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+    volumes: [data:/var/lib/postgresql/data]
+volumes:
+  data: {}
+",
+    );
+    fs::create_dir_all(fake.dir.join("volumes/data")).unwrap();
+    fs::write(fake.dir.join("volumes/data/PG_VERSION"), "16").unwrap();
+    fs::write(fake.dir.join("broken.tar.gz"), "not an archive").unwrap();
+
+    manager
+        .volume_restore("data", &fake.dir.join("broken.tar.gz"), true, &NullReporter)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        fs::read_to_string(fake.dir.join("volumes/data/PG_VERSION")).unwrap(),
+        "16"
+    );
+
+    // A good archive replaces the data, leaving nothing staged behind
+    fs::create_dir_all(fake.dir.join("backup")).unwrap();
+    fs::write(fake.dir.join("backup/PG_VERSION"), "17").unwrap();
+    let status = std::process::Command::new("tar")
+        .arg("-czf")
+        .arg(fake.dir.join("good.tar.gz"))
+        .arg("-C")
+        .arg(fake.dir.join("backup"))
+        .arg(".")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    manager
+        .volume_restore("data", &fake.dir.join("good.tar.gz"), true, &NullReporter)
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(fake.dir.join("volumes/data/PG_VERSION")).unwrap(),
+        "17"
+    );
+    let entries: Vec<_> = fs::read_dir(fake.dir.join("volumes")).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+}
+
+#[tokio::test]
+async fn stop_keeps_containers_but_removes_orphans() {
+    let fake = FakeContainer::new("stop-orphans");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    // Left behind by a service since renamed out of the config
+    fs::write(
+        fake.dir.join("state/proj-old"),
+        r#"running busybox {"container-compose.project":"proj","container-compose.service":"old"}"#,
+    )
+    .unwrap();
+
+    let options = StopOptions {
+        remove_orphans: true,
+        ..StopOptions::default()
+    };
+    manager
+        .stop(None, &options, &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        fake.targets_of("stop"),
+        ["proj-old", "proj-web", "proj-api", "proj-db"]
+    );
+    assert_eq!(fake.targets_of("rm"), ["proj-old"]);
+}
+
+#[tokio::test]
+async fn start_restarts_stopped_containers_without_creating_any() {
+    let fake = FakeContainer::new("start-stopped");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    manager
+        .stop(Some("web"), &StopOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    fs::remove_file(fake.dir.join("state/proj-db")).unwrap();
+
+    manager.start(None, &NullReporter).await.unwrap();
+
+    assert_eq!(fake.targets_of("start"), ["proj-web"]);
+    assert_eq!(fake.started(), ["proj-db", "proj-api", "proj-web"]);
+    assert!(matches!(
+        manager.start(Some("nope"), &NullReporter).await,
+        Err(ComposeError::ServiceNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn ps_shows_health_of_services_with_healthchecks() {
+    let fake = FakeContainer::new("ps-health");
+    let mut manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+    healthcheck:
+      test: [CMD, pg_isready]
+  api:
+    image: node
+    healthcheck:
+      test: [CMD, curl, -f, http://localhost/]
+  cache:
+    image: redis
+    healthcheck:
+      test: [CMD, redis-cli, ping]
+  web:
+    image: nginx
+",
+    );
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    fake.canned("exec proj-db pg_isready", "no response");
+    fs::write(fake.dir.join("canned/exec proj-db pg_isready.exit"), "1").unwrap();
+    fake.canned(
+        "inspect proj-api",
+        r#"[{"status":"running","health":{"status":"starting"}}]"#,
+    );
+
+    let reporter = CollectingReporter::new();
+    manager.ps(&PsOptions::default(), &reporter).await.unwrap();
+
+    let statuses: Vec<String> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::TableRow { cells } => Some(cells[1].clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            "Running (unhealthy)",
+            "Running (starting)",
+            "Running (healthy)",
+            "Running"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn ps_shows_failing_checks_within_the_start_period_as_starting() {
+    let fake = FakeContainer::new("ps-start-period");
+    let mut manager = fake.manager(
+        "
+services:
+  db:
+    image: postgres
+    healthcheck:
+      test: [CMD, pg_isready]
+      start_period: 1h
+  cache:
+    image: redis
+    healthcheck:
+      test: [CMD, redis-cli, ping]
+",
+    );
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    for (container, check) in [("proj-db", "pg_isready"), ("proj-cache", "redis-cli ping")] {
+        let argv = format!("exec {} {}", container, check);
+        fake.canned(&argv, "no response");
+        fs::write(fake.dir.join(format!("canned/{}.exit", argv)), "1").unwrap();
+    }
+
+    let reporter = CollectingReporter::new();
+    manager.ps(&PsOptions::default(), &reporter).await.unwrap();
+
+    let statuses: Vec<String> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::TableRow { cells } => Some(cells[1].clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(statuses, ["Running (starting)", "Running (unhealthy)"]);
+}
+
+#[tokio::test]
+async fn ps_script_output_goes_through_the_reporter() {
+    let fake = FakeContainer::new("ps-output");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    let reporter = CollectingReporter::new();
+    let options = PsOptions {
+        quiet: true,
+        ..PsOptions::default()
+    };
+    manager.ps(&options, &reporter).await.unwrap();
+
+    assert_eq!(
+        reporter.messages(),
+        ["proj-web", "proj-api", "proj-db"]
+            .map(|line| Message::Output {
+                line: line.to_string()
+            })
+            .to_vec()
+    );
+}
+
+#[tokio::test]
+async fn ps_maps_replicas_and_named_containers_to_their_services() {
+    let fake = FakeContainer::new("ps-replicas");
+    let mut manager = fake.manager(
+        "
+services:
+  web:
+    image: nginx
+  api:
+    image: node
+  api-1:
+    image: node
+  worker:
+    image: node
+    container_name: jobs
+",
+    );
+    let options = UpOptions {
+        scale: [("web".to_string(), 2)].into(),
+        ..UpOptions::default()
+    };
+    manager.up(&options, &NullReporter, false).await.unwrap();
+
+    let reporter = CollectingReporter::new();
+    manager.ps(&PsOptions::default(), &reporter).await.unwrap();
+
+    let rows: Vec<(String, String)> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::TableRow { cells } => Some((cells[0].clone(), cells[2].clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("web", "proj-web-1"),
+            ("web", "proj-web-2"),
+            ("api", "proj-api"),
+            ("api-1", "proj-api-1"),
+            ("worker", "jobs"),
+        ]
+        .map(|(service, container)| (service.to_string(), container.to_string()))
+    );
+}
+
+#[tokio::test]
+async fn ps_orphans_lists_leftovers_without_removing_them() {
+    let fake = FakeContainer::new("ps-orphans");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    fs::write(
+        fake.dir.join("state/proj-old"),
+        r#"stopped busybox {"container-compose.project":"proj","container-compose.service":"old"}"#,
+    )
+    .unwrap();
+
+    let reporter = CollectingReporter::new();
+    let options = PsOptions {
+        orphans: true,
+        ..PsOptions::default()
+    };
+    manager.ps(&options, &reporter).await.unwrap();
+
+    let rows: Vec<Vec<String>> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::TableRow { cells } => Some(cells),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(rows, [["old", "Stopped", "proj-old", "busybox"]]);
+    assert!(fake.targets_of("rm").is_empty());
+    assert!(fake.dir.join("state/proj-old").exists());
+}
+
+#[tokio::test]
+async fn logs_grep_filters_every_stream_after_the_prefix() {
+    let fake = FakeContainer::new("logs-grep");
+    let mut manager = fake.manager(CHAIN);
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    fake.canned("logs -n 5 proj-db", "ready\nERROR: disk full\n");
+    fake.canned("logs -n 5 proj-api", "listening\nERROR: no db\n");
+    fake.canned("logs -n 5 proj-web", "GET /api 502\n");
+
+    let reporter = CollectingReporter::new();
+    let options = LogOptions {
+        tail: Some(5),
+        grep: Some("ERROR".to_string()),
+        ..LogOptions::default()
+    };
+    manager.logs(None, &options, &reporter).await.unwrap();
+
+    let mut lines: Vec<(Option<String>, String)> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::Log { service, line } => Some((service, line)),
+            _ => None,
+        })
+        .collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        [
+            (Some("api".to_string()), "ERROR: no db".to_string()),
+            (Some("db".to_string()), "ERROR: disk full".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn up_wait_fails_as_soon_as_a_container_exits() {
+    let fake = FakeContainer::new("wait-exit");
+    let mut manager = fake.manager(
+        "
+services:
+  web:
+    image: nginx
+    healthcheck:
+      test: [CMD, pgrep, nginx]
+      interval: 1h
+",
+    );
+    // The first check fails, leaving the next one an hour away
+    fake.canned("exec proj-web pgrep nginx", "");
+    fs::write(fake.dir.join("canned/exec proj-web pgrep nginx.exit"), "7").unwrap();
+    fake.canned(
+        "inspect proj-web",
+        r#"[{"status":"stopped","exitCode":137}]"#,
+    );
+    fake.canned("logs -n 20 proj-web", "out of memory\n");
+
+    let options = UpOptions {
+        wait: true,
+        ..UpOptions::default()
+    };
+    let reporter = CollectingReporter::new();
+    let crash = async {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        fake.exit("proj-web");
+    };
+    let (result, ()) = tokio::join!(manager.up(&options, &reporter, false), crash);
+
+    let err = result.unwrap_err();
+    assert!(
+        matches!(&err, ComposeError::ServiceExited { service, exit_code: 137 } if service == "web"),
+        "unexpected error: {}",
+        err
+    );
+    assert!(reporter.messages().contains(&Message::Log {
+        service: Some("web".to_string()),
+        line: "out of memory".to_string(),
+    }));
+}
+
+#[tokio::test]
+async fn wait_timeout_bounds_health_without_holding_each_start() {
+    let fake = FakeContainer::new("wait-timeout");
+    let mut manager = fake.manager(CHAIN);
+
+    let options = UpOptions {
+        wait_timeout: Some(120),
+        ..UpOptions::default()
+    };
+    let started = std::time::Instant::now();
+    manager.up(&options, &NullReporter, false).await.unwrap();
+
+    // Services without a healthcheck are ready once running
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    assert_eq!(fake.started(), ["proj-db", "proj-api", "proj-web"]);
+}
+
+#[test]
+fn include_merges_files_under_the_including_one() {
+    let fake = FakeContainer::new("include");
+    fs::create_dir_all(fake.dir.join("shared")).unwrap();
+    fs::write(
+        fake.dir.join("shared/backend.yml"),
+        "
+include: [cache.yml]
+services:
+  db:
+    image: postgres:15
+  api:
+    image: node
+    depends_on: [db]
+",
+    )
+    .unwrap();
+    fs::write(
+        fake.dir.join("shared/cache.yml"),
+        "
+services:
+  cache:
+    image: redis
+",
+    )
+    .unwrap();
+    let file = fake.dir.join("container-compose.yml");
+    fs::write(
+        &file,
+        "
+include: [shared/backend.yml]
+services:
+  db:
+    image: postgres:16
+  web:
+    image: nginx
+    depends_on: [api]
+",
+    )
+    .unwrap();
+
+    let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+
+    let services: Vec<&String> = config.services.keys().collect();
+    assert_eq!(services, ["cache", "db", "api", "web"]);
+    assert_eq!(config.services["db"].image, "postgres:16");
+    assert_eq!(config.include, ["shared/backend.yml"]);
+}
+
+#[test]
+fn include_resolves_relative_paths_against_the_included_file() {
+    let fake = FakeContainer::new("include-paths");
+    fs::create_dir_all(fake.dir.join("shared")).unwrap();
+    fs::write(
+        fake.dir.join("shared/backend.yml"),
+        "
+services:
+  api:
+    build:
+      dockerfile: Dockerfile.dev
+    volumes:
+      - ./data:/data
+      - cache:/cache
+      - /etc/hosts:/etc/hosts:ro
+  worker:
+    image: node
+    build: ../worker
+configs:
+  settings:
+    file: settings.json
+",
+    )
+    .unwrap();
+    let file = fake.dir.join("container-compose.yml");
+    fs::write(
+        &file,
+        "
+include: [shared/backend.yml]
+services:
+  web:
+    image: nginx
+    volumes:
+      - ./site:/usr/share/nginx/html
+volumes:
+  cache: {}
+",
+    )
+    .unwrap();
+
+    let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+
+    let api = &config.services["api"];
+    assert_eq!(api.build.as_ref().unwrap().context, "./shared");
+    assert_eq!(
+        api.volumes,
+        [
+            "./shared/data:/data",
+            "cache:/cache",
+            "/etc/hosts:/etc/hosts:ro"
+        ]
+    );
+    assert_eq!(
+        config.services["worker"].build.as_ref().unwrap().context,
+        "./shared/../worker"
+    );
+    assert_eq!(config.configs["settings"].file, "./shared/settings.json");
+    assert_eq!(
+        config.services["web"].volumes,
+        ["./site:/usr/share/nginx/html"]
+    );
+}
+
+#[test]
+fn include_cycles_are_reported_with_the_chain() {
+    let fake = FakeContainer::new("include-cycle");
+    fs::write(fake.dir.join("a.yml"), "include: [b.yml]\nservices: {}\n").unwrap();
+    fs::write(fake.dir.join("b.yml"), "include: [a.yml]\nservices: {}\n").unwrap();
+
+    let err =
+        ContainerComposeConfig::from_file(&fake.dir.join("a.yml").to_string_lossy()).unwrap_err();
+
+    let ComposeError::InvalidInclude(reason) = err else {
+        panic!("expected InvalidInclude, got {}", err);
+    };
+    let dir = fake.dir.canonicalize().unwrap();
+    assert_eq!(
+        reason,
+        format!(
+            "Circular include detected: {0}/a.yml -> {0}/b.yml -> {0}/a.yml",
+            dir.display()
+        )
+    );
+}
+
+#[test]
+fn bare_volume_sources_must_be_declared_volumes() {
+    let fake = FakeContainer::new("bare-volume");
+    let file = fake.dir.join("container-compose.yml");
+    fs::write(
+        &file,
+        "
+services:
+  db:
+    image: postgres
+    volumes:
+      - data:/var/lib/postgresql/data
+      - cache:/cache
+      - ./conf:/etc/postgresql
+      - logs/db:/var/log
+volumes:
+  cache: {}
+",
+    )
+    .unwrap();
+
+    let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+    let errors = config.validate().unwrap_err();
+
+    let messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+    assert_eq!(
+        messages,
+        ["Service 'db' references undefined volume 'data'"]
+    );
+}
+
+#[test]
+fn versions_keep_their_minor_number() {
+    let fake = FakeContainer::new("version");
+    let file = fake.dir.join("container-compose.yml");
+    let load = |version: &str| {
+        fs::write(&file, format!("version: {}\nservices: {{}}\n", version)).unwrap();
+        ContainerComposeConfig::from_file(&file.to_string_lossy()).map(|config| config.version)
+    };
+
+    assert_eq!(load("3.10").unwrap(), "3.10");
+    assert_eq!(load("3.8 # pinned").unwrap(), "3.8");
+    assert_eq!(load("'3.10'").unwrap(), "3.10");
+    assert_eq!(load("3").unwrap(), "3.0");
+    assert!(load("three").is_err());
+}
+
+#[test]
+fn config_modes_are_octal_and_configs_keep_their_order() {
+    let fake = FakeContainer::new("config-mode");
+    let file = fake.dir.join("container-compose.yml");
+    let load = |mode: &str| {
+        fs::write(
+            &file,
+            format!(
+                "
+services:
+  web:
+    image: nginx
+    configs:
+      - source: site
+        mode: {}
+configs:
+  site:
+    file: ./site.conf
+  app:
+    file: ./app.conf
+  base:
+    file: ./base.conf
+",
+                mode
+            ),
+        )
+        .unwrap();
+        ContainerComposeConfig::from_file(&file.to_string_lossy())
+    };
+
+    assert_eq!(
+        load("0440").unwrap().services["web"].configs[0].mode,
+        Some(0o440)
+    );
+    assert_eq!(
+        load("440").unwrap().services["web"].configs[0].mode,
+        Some(0o440)
+    );
+    assert_eq!(
+        load("'0o440'").unwrap().services["web"].configs[0].mode,
+        Some(0o440)
+    );
+    assert!(load("480").is_err());
+    assert_eq!(
+        load("0440").unwrap().configs.keys().collect::<Vec<_>>(),
+        ["site", "app", "base"]
+    );
+}
+
+#[tokio::test]
+async fn empty_commands_fall_back_to_the_image_default_and_empty_entrypoints_clear_it() {
+    let fake = FakeContainer::new("empty-command");
+    let compose = "
+services:
+  web:
+    image: nginx
+    command: []
+  db:
+    image: postgres
+    entrypoint: []
+    command: [postgres, -c, fsync=off]
+";
+    let mut manager = fake.manager(compose);
+    let file = fake.dir.join("container-compose.yml");
+    let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+
+    assert_eq!(config.services["web"].command, None);
+    assert_eq!(config.services["db"].entrypoint, Some(Vec::new()));
+    let warnings = config.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("Service 'web' sets an empty command"));
+
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+    let runs: Vec<String> = fake
+        .calls()
+        .into_iter()
+        .filter(|call| call.starts_with("run "))
+        .collect();
+    assert!(runs[0].ends_with(" nginx"));
+    assert!(runs[1].ends_with("--entrypoint  postgres postgres -c fsync=off"));
+}
+
+#[tokio::test]
+async fn update_only_touches_services_that_are_up() {
+    let fake = FakeContainer::new("update-up-only");
+    let mut manager = fake.manager(CHAIN);
+    fs::create_dir_all(fake.dir.join("state")).unwrap();
+    fs::write(
+        fake.dir.join("state/proj-db"),
+        r#"running postgres {"container-compose.project":"proj","container-compose.service":"db"}"#,
+    )
+    .unwrap();
+    for image in ["postgres", "nginx"] {
+        fake.canned(
+            &format!("images inspect {image}"),
+            r#"[{"digest":"sha256:aaaa"}]"#,
+        );
+        fs::write(
+            fake.dir.join(format!("canned/images inspect {image}.next")),
+            r#"[{"digest":"sha256:bbbb"}]"#,
+        )
+        .unwrap();
+    }
+
+    manager
+        .update(None, true, &NullReporter, false)
+        .await
+        .unwrap();
+
+    assert_eq!(fake.started(), ["proj-db"]);
+    assert_eq!(fake.targets_of("stop"), ["proj-db"]);
+}
+
+#[tokio::test]
+async fn pull_summarizes_which_images_changed() {
+    let fake = FakeContainer::new("pull-summary");
+    let manager = fake.manager(CHAIN);
+    fake.canned("images inspect postgres", r#"[{"digest":"sha256:aaaa"}]"#);
+    fs::write(
+        fake.dir.join("canned/images inspect postgres.next"),
+        r#"[{"digest":"sha256:bbbb"}]"#,
+    )
+    .unwrap();
+    fake.canned("images inspect node", r#"[{"digest":"sha256:cccc"}]"#);
+
+    let reporter = CollectingReporter::new();
+    manager.pull(None, false, false, &reporter).await.unwrap();
+
+    let rows: Vec<Vec<String>> = reporter
+        .messages()
+        .into_iter()
+        .filter_map(|message| match message {
+            Message::TableRow { cells } => Some(cells),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ["postgres", "pulled", "sha256:bbbb"],
+            ["node", "up to date", "sha256:cccc"],
+            ["nginx", "pulled", "-"],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn bare_environment_keys_pass_host_values_through() {
+    let fake = FakeContainer::new("env-passthrough");
+    let mut manager = fake.manager(
+        "
+services:
+  list:
+    image: busybox
+    environment: [HOME, CONTAINER_COMPOSE_TEST_UNSET, MODE=list]
+  map:
+    image: busybox
+    environment:
+      HOME:
+      CONTAINER_COMPOSE_TEST_UNSET: ~
+",
+    );
+
+    manager
+        .up(&UpOptions::default(), &NullReporter, false)
+        .await
+        .unwrap();
+
+    let home = std::env::var("HOME").unwrap();
+    let envs: Vec<Vec<String>> = fake
+        .calls()
+        .iter()
+        .filter(|call| call.starts_with("run "))
+        .map(|call| {
+            let args: Vec<&str> = call.split(' ').collect();
+            args.windows(2)
+                .filter(|pair| pair[0] == "--env")
+                .map(|pair| pair[1].to_string())
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        envs,
+        [
+            vec![format!("HOME={}", home), "MODE=list".to_string()],
+            vec![format!("HOME={}", home)],
+        ]
+    );
+}
+
+#[test]
+fn convert_leaves_out_the_image_of_build_only_services() {
+    let fake = FakeContainer::new("convert-build-only");
+    let file = fake.dir.join("container-compose.yml");
+    fs::write(
+        &file,
+        "
+services:
+  app:
+    build: .
+  db:
+    image: postgres
+",
+    )
+    .unwrap();
+    let config = ContainerComposeConfig::from_file(&file.to_string_lossy()).unwrap();
+
+    let (compose, _) = DockerCompose::from_config(&config);
+    let yaml: serde_yaml::Value = serde_yaml::to_value(&compose).unwrap();
+    assert!(yaml["services"]["app"].get("image").is_none());
+    assert!(yaml["services"]["app"].get("build").is_some());
+    assert_eq!(yaml["services"]["db"]["image"], "postgres");
+}